@@ -14,6 +14,9 @@ import android.os.AsyncTask;
 
 import androidx.annotation.Nullable;
 
+import org.json.JSONException;
+import org.json.JSONObject;
+
 import app.ironbelly.tor.TorConfig;
 import app.ironbelly.tor.TorProxyManager;
 import app.ironbelly.tor.TorProxyState;
@@ -22,6 +25,8 @@ import kotlin.Unit;
 public class GrinBridge extends ReactContextBaseJavaModule {
 
     private Long openedWallet;
+    // Hex encoded mask of a wallet opened through `call` with `use_mask`
+    private String keychainMask;
     private Long httpListenerApi;
     private TorProxyManager torProxyManager;
 
@@ -53,6 +58,7 @@ public class GrinBridge extends ReactContextBaseJavaModule {
             public void run() {
                 try {
                     openedWallet = openWallet(config, password);
+                    keychainMask = null;
                     promise.resolve("Opened wallet successfully");
                 } catch (Exception e) {
                     promise.reject("", e.getMessage());
@@ -70,6 +76,7 @@ public class GrinBridge extends ReactContextBaseJavaModule {
                     try {
                         String result = closeWallet(openedWallet);
                         openedWallet = null;
+                        keychainMask = null;
                         promise.resolve(result);
                     } catch (Exception e) {
                         promise.reject("", e.getMessage());
@@ -79,6 +86,42 @@ public class GrinBridge extends ReactContextBaseJavaModule {
         });
     }
 
+    @ReactMethod
+    public void call(String method, String params, Promise promise) {
+        AsyncTask.execute(new Runnable() {
+            @Override
+            public void run() {
+                try {
+                    String result = call(openedWallet == null ? 0 : openedWallet, method, withKeychainMask(params));
+                    if (method.equals("open_wallet")) {
+                        // Later calls go to the opened wallet and carry its mask
+                        JSONObject response = new JSONObject(result);
+                        openedWallet = response.getLong("wallet");
+                        keychainMask = response.isNull("keychain_mask") ? null : response.getString("keychain_mask");
+                    } else if (method.equals("close_wallet")) {
+                        openedWallet = null;
+                        keychainMask = null;
+                    }
+                    promise.resolve(result);
+                } catch (Exception e) {
+                    promise.reject("", e.getMessage());
+                }
+            }
+        });
+    }
+
+    // Add the mask of the opened wallet to call params which don't set one
+    private String withKeychainMask(String params) throws JSONException {
+        if (keychainMask == null) {
+            return params;
+        }
+        JSONObject object = params.trim().isEmpty() ? new JSONObject() : new JSONObject(params);
+        if (!object.has("keychain_mask")) {
+            object.put("keychain_mask", keychainMask);
+        }
+        return object.toString();
+    }
+
     @ReactMethod
     public void setLogger(Promise promise) {
         AsyncTask.execute(new Runnable() {
@@ -121,6 +164,20 @@ public class GrinBridge extends ReactContextBaseJavaModule {
         });
     }
 
+    @ReactMethod
+    public void walletChangePassword(String config, String oldPassword, String newPassword, Promise promise) {
+        AsyncTask.execute(new Runnable() {
+            @Override
+            public void run() {
+                try {
+                    promise.resolve(walletChangePassword(config, oldPassword, newPassword));
+                } catch (Exception e) {
+                    promise.reject("", e.getMessage());
+                }
+            }
+        });
+    }
+
     @ReactMethod
     public void txGet(Boolean refreshFromNode, String txSlateId, Promise promise) {
         AsyncTask.execute(new Runnable() {
@@ -441,6 +498,8 @@ public class GrinBridge extends ReactContextBaseJavaModule {
 
     private static native String setLogger();
 
+    private static native String call(long handle, String method, String params);
+
     private static native String balance(String state, boolean refreshFromNode);
 
     private static native String txGet(long openedWallet, boolean refreshFromNode, String txSlateId);
@@ -451,6 +510,8 @@ public class GrinBridge extends ReactContextBaseJavaModule {
 
     private static native String walletInit(String config, String phrase, String password);
 
+    private static native String walletChangePassword(String config, String oldPassword, String newPassword);
+
     private static native long openWallet(String config, String password);
 
     private static native String closeWallet(long openedWallet);
@@ -18,8 +18,12 @@
 
 @interface RCT_EXTERN_MODULE(GrinBridge, NSObject)
 
+RCT_EXTERN_METHOD(call:(NSString*)method params:(NSString*)params resolve:(RCTPromiseResolveBlock)resolve reject:(RCTPromiseRejectBlock)reject)
+
 RCT_EXTERN_METHOD(walletInit:(NSString*)config phrase:(NSString*)phrase password:(NSString*)password resolve:(RCTPromiseResolveBlock)resolve reject:(RCTPromiseRejectBlock)reject)
 
+RCT_EXTERN_METHOD(walletChangePassword:(NSString*)config oldPassword:(NSString*)oldPassword newPassword:(NSString*)newPassword resolve:(RCTPromiseResolveBlock)resolve reject:(RCTPromiseRejectBlock)reject)
+
 RCT_EXTERN_METHOD(openWallet:(NSString*)config password:(NSString*)password resolve:(RCTPromiseResolveBlock)resolve reject:(RCTPromiseRejectBlock)reject)
 
 RCT_EXTERN_METHOD(closeWallet:(RCTPromiseResolveBlock)resolve reject:(RCTPromiseRejectBlock)reject)
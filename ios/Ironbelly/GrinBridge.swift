@@ -24,22 +24,77 @@ class GrinBridge: RCTEventEmitter {
     
     var httpListenerApi: UInt? // UnsafeMutablePointer<api_server>?
     var openedWallet: UInt?
+    // Hex encoded mask of a wallet opened through `call` with `use_mask`
+    var keychainMask: String?
     
     @objc override static func requiresMainQueueSetup() -> Bool {
         return false
     }
     
+    @objc func call(_ method: String, params: String, resolve: RCTPromiseResolveBlock, reject: RCTPromiseRejectBlock) -> Void {
+        var error: UInt8 = 0
+        let cResult = c_call(openedWallet ?? 0, method, withKeychainMask(params), &error)
+        if error == 0 {
+            let result = String(cString: cResult!)
+            switch method {
+            case "open_wallet":
+                // Later calls go to the opened wallet and carry its mask
+                if let data = result.data(using: .utf8),
+                   let response = try? JSONSerialization.jsonObject(with: data) as? [String: Any],
+                   let wallet = response["wallet"] as? NSNumber {
+                    openedWallet = wallet.uintValue
+                    keychainMask = response["keychain_mask"] as? String
+                }
+            case "close_wallet":
+                openedWallet = nil
+                keychainMask = nil
+            default:
+                break
+            }
+        }
+        returnToReact(error:error, cResult:cResult!, resolve: resolve, reject: reject)
+    }
+
+    // Add the mask of the opened wallet to call params which don't set one
+    func withKeychainMask(_ params: String) -> String {
+        guard let mask = keychainMask else {
+            return params
+        }
+        var object: [String: Any] = [:]
+        if let data = params.data(using: .utf8),
+           let parsed = try? JSONSerialization.jsonObject(with: data) as? [String: Any] {
+            object = parsed
+        } else if !params.trimmingCharacters(in: .whitespaces).isEmpty {
+            return params
+        }
+        if object["keychain_mask"] == nil {
+            object["keychain_mask"] = mask
+        }
+        guard let data = try? JSONSerialization.data(withJSONObject: object),
+              let json = String(data: data, encoding: .utf8) else {
+            return params
+        }
+        return json
+    }
+    
     @objc func walletInit(_ config:String, phrase: String, password: String, resolve: RCTPromiseResolveBlock, reject: RCTPromiseRejectBlock) -> Void {
         var error: UInt8 = 0
         let cResult = c_wallet_init(config, phrase, password, &error)
         returnToReact(error:error, cResult:cResult!, resolve: resolve, reject: reject)
     }
     
+    @objc func walletChangePassword(_ config:String, oldPassword: String, newPassword: String, resolve: RCTPromiseResolveBlock, reject: RCTPromiseRejectBlock) -> Void {
+        var error: UInt8 = 0
+        let cResult = c_wallet_change_password(config, oldPassword, newPassword, &error)
+        returnToReact(error:error, cResult:cResult!, resolve: resolve, reject: reject)
+    }
+    
     @objc func openWallet(_ config:String, password: String, resolve: RCTPromiseResolveBlock, reject: RCTPromiseRejectBlock) -> Void {
         var error: UInt8 = 0
         let cResult = c_open_wallet(config, password, &error)
         if (error == 0) {
             openedWallet = cResult
+            keychainMask = nil
             resolve(true)
         } else {
             reject(nil, String(cString: UnsafePointer<UInt8>(bitPattern: cResult)!), nil)
@@ -52,6 +107,7 @@ class GrinBridge: RCTEventEmitter {
             let cResult = c_close_wallet(wallet, &error)
             if (error == 0) {
                 openedWallet = nil
+                keychainMask = nil
             }
             returnToReact(error:error, cResult:cResult! , resolve: resolve, reject: reject)
         }
@@ -189,12 +245,14 @@ class GrinBridge: RCTEventEmitter {
         var error: UInt8 = 0
         if httpListenerApi == nil {
             if let wallet = checkOpenedWallet(openedWallet, reject) {
-                httpListenerApi = c_start_listen_with_http(wallet,apiListenAddress, &error)
+                let result = c_start_listen_with_http(wallet,apiListenAddress, &error)
                 if error == 0 {
+                    httpListenerApi = result
                     let cResult = c_get_grin_address(wallet, &error)
                     returnToReact(error:error, cResult:cResult!, resolve: resolve, reject: reject)
                 } else {
-                    reject(nil, "Can not start HTTP server. See logs.", nil)
+                    // The error JSON is returned in place of the handle
+                    returnToReact(error:error, cResult:UnsafePointer<Int8>(bitPattern: result)!, resolve: resolve, reject: reject)
                 }
             }
         } else {
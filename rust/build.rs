@@ -13,12 +13,18 @@
  * See the License for the specific language governing permissions and
  * limitations under the License.
  */
+#[cfg(feature = "ios")]
 extern crate cbindgen;
 
-use std::env;
-
 fn main() {
-    let crate_dir = env::var("CARGO_MANIFEST_DIR").unwrap();
+    #[cfg(feature = "ios")]
+    generate_c_header();
+}
+
+/// Header for the C ABI exported by the `ios` feature
+#[cfg(feature = "ios")]
+fn generate_c_header() {
+    let crate_dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
 
     cbindgen::Builder::new()
         .with_crate(crate_dir)
@@ -0,0 +1,306 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// Accounts are labelled BIP32 paths (m/0/0, m/1/0, ...) inside one wallet,
+// each with its own outputs and balance.
+use crate::errors::{Error, ErrorKind};
+use crate::responses::{AccountResponse, AccountsListResponse, TxTransferResponse};
+use crate::{check_middleware, slate_txs, Config, SecretKey, WalletInstance, DEFAULT_ACCOUNT};
+use grin_keychain::Identifier;
+use grin_wallet_api::{Foreign, Owner};
+use grin_wallet_libwallet::{
+    tx, updater, wallet_lock, AcctPathMapping, InitTxArgs, NodeClient, Slate, WalletInfo,
+};
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+/// Confirmations the balance of a single account is computed
+/// with, `accounts_list` takes any other number
+const MINIMUM_CONFIRMATIONS: u64 = 1;
+
+/// Account of a wallet with its balance
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Account {
+    pub label: String,
+    /// BIP32 path of the account, e.g. `m/1/0`
+    pub path: String,
+    #[cfg_attr(
+        feature = "schema",
+        schemars(with = "crate::responses::schema::WalletInfo")
+    )]
+    pub info: WalletInfo,
+}
+
+/// Accounts of the wallet, ordered by label
+pub fn accounts_list<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    minimum_confirmations: u64,
+) -> Result<AccountsListResponse, Error> {
+    let api = Owner::new(wallet.clone(), None);
+    let mut accounts = vec![];
+    for mapping in api.accounts(keychain_mask)? {
+        accounts.push(account(
+            wallet,
+            mapping.label,
+            &mapping.path,
+            minimum_confirmations,
+        )?);
+    }
+    Ok(AccountsListResponse::new(accounts))
+}
+
+/// Create an account on the next free BIP32 path
+pub fn account_create<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    label: &str,
+) -> Result<AccountResponse, Error> {
+    check_label(label)?;
+    let api = Owner::new(wallet.clone(), None);
+    let path = api.create_account_path(keychain_mask, label)?;
+    Ok(AccountResponse::new(account(
+        wallet,
+        label.to_owned(),
+        &path,
+        MINIMUM_CONFIRMATIONS,
+    )?))
+}
+
+/// Change the label of an account, its path and outputs stay the same
+pub fn account_rename<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    old_label: &str,
+    new_label: &str,
+) -> Result<AccountResponse, Error> {
+    check_label(new_label)?;
+    // Wallets are opened with the default account if no other is given
+    if old_label == DEFAULT_ACCOUNT {
+        return Err(argument_error("the default account can't be renamed"));
+    }
+    let path = {
+        wallet_lock!(wallet, w);
+        let accounts: Vec<AcctPathMapping> = w.acct_path_iter().collect();
+        if accounts.iter().any(|a| a.label == new_label) {
+            return Err(grin_wallet_libwallet::Error::AccountLabelAlreadyExists(
+                new_label.to_owned(),
+            )
+            .into());
+        }
+        let path = match accounts.iter().find(|a| a.label == old_label) {
+            Some(mapping) => mapping.path.clone(),
+            None => {
+                return Err(
+                    grin_wallet_libwallet::Error::UnknownAccountLabel(old_label.to_owned()).into(),
+                )
+            }
+        };
+        let renamed = AcctPathMapping {
+            label: new_label.to_owned(),
+            path: path.clone(),
+        };
+        let mut batch = w.batch(keychain_mask)?;
+        batch.rename_acct_path(accounts, &renamed)?;
+        batch.commit()?;
+        path
+    };
+    Ok(AccountResponse::new(account(
+        wallet,
+        new_label.to_owned(),
+        &path,
+        MINIMUM_CONFIRMATIONS,
+    )?))
+}
+
+/// Switch the account calls on the opened wallet work with, e.g. addresses,
+/// balances and new transactions, without reopening the wallet
+pub fn wallet_set_active_account<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    label: &str,
+) -> Result<AccountResponse, Error> {
+    let path = {
+        wallet_lock!(wallet, w);
+        if w.set_parent_key_id_by_name(label).is_err() {
+            return Err(ErrorKind::AccountNotFound(label.to_owned()).into());
+        }
+        w.parent_key_id()
+    };
+    Ok(AccountResponse::new(account(
+        wallet,
+        label.to_owned(),
+        &path,
+        MINIMUM_CONFIRMATIONS,
+    )?))
+}
+
+/// Move funds from one account of the wallet to another. The transaction
+/// is created, received, finalized and posted in one go, so the sent and
+/// the received entry end up in the tx log of the two accounts.
+#[allow(clippy::too_many_arguments)]
+pub fn tx_transfer_between_accounts<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    config: &Config,
+    from: &str,
+    to: &str,
+    amount: u64,
+    minimum_confirmations: u64,
+    selection_strategy_is_use_all: bool,
+) -> Result<TxTransferResponse, Error> {
+    tx_transfer_between_accounts_with_finalize(
+        wallet,
+        keychain_mask,
+        config,
+        from,
+        to,
+        amount,
+        minimum_confirmations,
+        selection_strategy_is_use_all,
+        |slate| Ok(Owner::new(wallet.clone(), None).finalize_tx(keychain_mask, slate)?),
+    )
+}
+
+/// Same as `tx_transfer_between_accounts`, but the received slate is
+/// finalized with `finalize`, so tests can make that step fail
+#[doc(hidden)]
+#[allow(clippy::too_many_arguments)]
+pub fn tx_transfer_between_accounts_with_finalize<C, F>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    config: &Config,
+    from: &str,
+    to: &str,
+    amount: u64,
+    minimum_confirmations: u64,
+    selection_strategy_is_use_all: bool,
+    finalize: F,
+) -> Result<TxTransferResponse, Error>
+where
+    C: NodeClient + 'static,
+    F: FnOnce(&Slate) -> Result<Slate, Error>,
+{
+    if from == to {
+        return Err(argument_error("can't transfer to the same account"));
+    }
+    let paths = {
+        wallet_lock!(wallet, w);
+        // The Owner API falls back to the active account for unknown labels
+        let mut paths = vec![];
+        for label in &[from, to] {
+            match w.acct_path_iter().find(|a| a.label == *label) {
+                Some(account) => paths.push((*label, account.path)),
+                None => return Err(ErrorKind::AccountNotFound((*label).to_owned()).into()),
+            }
+        }
+        paths
+    };
+
+    let owner_api = Owner::new(wallet.clone(), None);
+    let args = InitTxArgs {
+        src_acct_name: Some(from.to_owned()),
+        amount,
+        minimum_confirmations,
+        max_outputs: config.max_outputs as u32,
+        num_change_outputs: config.num_change_outputs as u32,
+        selection_strategy_is_use_all,
+        ..Default::default()
+    };
+    let slate = owner_api.init_send_tx(keychain_mask, args)?;
+    owner_api.tx_lock_outputs(keychain_mask, &slate)?;
+    let finalized = (|| -> Result<Slate, Error> {
+        let foreign_api = Foreign::new(
+            wallet.clone(),
+            keychain_mask.cloned(),
+            Some(check_middleware),
+            false,
+        );
+        let slate = foreign_api.receive_tx(&slate, Some(to), None)?;
+        finalize(&slate)
+    })();
+    let finalized = match finalized {
+        Ok(slate) => slate,
+        Err(e) => {
+            // Unlock the inputs again, nothing has left the wallet yet. The
+            // Owner API would only look in the active account.
+            for (label, parent_key_id) in &paths {
+                if let Err(cancel_error) =
+                    cancel_in_account(wallet, keychain_mask, parent_key_id, slate.id)
+                {
+                    warn!(
+                        "Failed to cancel transfer {} in account {}: {}",
+                        slate.id, label, cancel_error
+                    );
+                }
+            }
+            return Err(e);
+        }
+    };
+    // A failed post can be retried with `tx_post`, which finds the
+    // transaction whichever of the accounts is active
+    owner_api.post_tx(keychain_mask, finalized.tx_or_err()?, true)?;
+
+    Ok(TxTransferResponse::new(slate_txs(wallet, finalized.id)?))
+}
+
+/// Cancel the entry of a transaction in the account, if it has one
+fn cancel_in_account<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    parent_key_id: &Identifier,
+    slate_id: Uuid,
+) -> Result<(), Error> {
+    wallet_lock!(wallet, w);
+    let txs = updater::retrieve_txs(
+        &mut **w,
+        None,
+        Some(slate_id),
+        None,
+        Some(parent_key_id),
+        false,
+    )?;
+    // The receiving account has none if receiving failed
+    if !txs.is_empty() {
+        tx::cancel_tx(&mut **w, keychain_mask, parent_key_id, None, Some(slate_id))?;
+    }
+    Ok(())
+}
+
+fn account<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    label: String,
+    path: &Identifier,
+    minimum_confirmations: u64,
+) -> Result<Account, Error> {
+    wallet_lock!(wallet, w);
+    let info = updater::retrieve_info(&mut **w, path, minimum_confirmations)?;
+    Ok(Account {
+        label,
+        path: path.to_bip_32_string(),
+        info,
+    })
+}
+
+fn check_label(label: &str) -> Result<(), Error> {
+    if label.trim().is_empty() {
+        return Err(argument_error("account label can't be empty"));
+    }
+    Ok(())
+}
+
+fn argument_error(message: &str) -> Error {
+    grin_wallet_libwallet::Error::ArgumentError(message.to_owned()).into()
+}
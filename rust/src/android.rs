@@ -20,35 +20,17 @@ use self::jni::objects::{JClass, JString};
 use self::jni::sys::{jlong, jstring};
 use self::jni::JNIEnv;
 use super::*;
-
-macro_rules! unwrap_to_jni (
-    ($env:expr, $func:expr) => (
-        match $func {
-            Ok(res) => {
-                $env.new_string(res).unwrap().into_inner()
-            }
-            Err(e) => {
-                let result = $env.new_string("").unwrap().into_inner();
-                $env.throw(serde_json::to_string(&format!("{}",e)).unwrap()).unwrap();
-                result
-            }
-        }
-    )
-);
+use errors::catch_panic;
+use handles::{unmasked_wallet, Handle, OpenedWallet, API_SERVERS, WALLETS};
 
 macro_rules! ensure_wallet (
-    ($wallet_ptr:expr, $wallet:ident, $env:expr) => (
-        if ($wallet_ptr as *mut Wallet).as_mut().is_none() {
-            let _ = $env.throw(serde_json::to_string(&format!("Wallet is NULL")).unwrap());
-            return $env.new_string("").unwrap().into_inner();
-        }
-        let $wallet = ($wallet_ptr as *mut Wallet).as_mut().unwrap();
-    )
-);
-
-macro_rules! get_string_from_jni (
-    ($str:ident, $env:expr) => (
-        let $str: String = $env.get_string($str).expect("Invalid string").into();
+    ($wallet_handle:expr, $wallet:ident, $config:ident) => (
+        // Kept until the end of the call, so the wallet isn't locked meanwhile
+        let opened = unmasked_wallet($wallet_handle as Handle)?;
+        let ($wallet, $config) = (opened.wallet.clone(), opened.config.clone());
+    );
+    ($wallet_handle:expr, $wallet:ident) => (
+        ensure_wallet!($wallet_handle, $wallet, _config);
     )
 );
 
@@ -57,18 +39,33 @@ pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_setLogger(
     env: JNIEnv,
     _: JClass,
 ) -> jstring {
-    android_logger::init_once(
-        android_logger::Config::default()
-            .with_min_level(if cfg!(debug_assertions) {
-                log::Level::Debug
-            } else {
-                log::Level::Info
-            })
-            .with_tag("Ironbelly"),
-    );
-    env.new_string("Logger initiated successfully!")
-        .unwrap()
-        .into_inner()
+    unwrap_to_jni!(env, {
+        android_logger::init_once(
+            android_logger::Config::default()
+                .with_min_level(if cfg!(debug_assertions) {
+                    log::Level::Debug
+                } else {
+                    log::Level::Info
+                })
+                .with_tag("Ironbelly"),
+        );
+        Ok("Logger initiated successfully!".to_owned())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_call(
+    env: JNIEnv,
+    _: JClass,
+    handle: jlong,
+    method: JString,
+    params: JString,
+) -> jstring {
+    unwrap_to_jni!(env, {
+        get_string_from_jni!(method, env);
+        get_string_from_jni!(params, env);
+        dispatch::call(handle as Handle, &method, &params)
+    })
 }
 
 #[no_mangle]
@@ -77,10 +74,7 @@ pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_seedNew(
     _: JClass,
     seed_length: jlong,
 ) -> jstring {
-    unwrap_to_jni!(
-        env,
-        WalletSeed::init_new(seed_length as usize, false, None).to_mnemonic()
-    )
+    unwrap_to_jni!(env, to_json(&seed_new(seed_length as usize)?))
 }
 
 #[no_mangle]
@@ -91,38 +85,64 @@ pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_walletInit(
     phrase: JString,
     password: JString,
 ) -> jstring {
-    get_string_from_jni!(config_str, env);
-    get_string_from_jni!(phrase, env);
-    get_string_from_jni!(password, env);
-    unwrap_to_jni!(env, wallet_init(&config_str, &phrase, &password))
+    unwrap_to_jni!(env, {
+        get_string_from_jni!(config_str, env);
+        get_string_from_jni!(phrase, env);
+        get_string_from_jni!(password, env);
+        wallet_init(&config_str, &phrase, &password)?;
+        to_json(&EmptyResponse::new())
+    })
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_walletChangePassword(
+    env: JNIEnv,
+    _: JClass,
+    config_str: JString,
+    old_password: JString,
+    new_password: JString,
+) -> jstring {
+    unwrap_to_jni!(env, {
+        get_string_from_jni!(config_str, env);
+        get_string_from_jni!(old_password, env);
+        get_string_from_jni!(new_password, env);
+        OpenedWallet::change_password(&config_str, &old_password, &new_password)?;
+        to_json(&EmptyResponse::new())
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_txGet(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
     refresh_from_node: bool,
     tx_slate_id: JString,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    get_string_from_jni!(tx_slate_id, env);
-    unwrap_to_jni!(env, tx_get(&wallet, refresh_from_node, &tx_slate_id))
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet);
+        get_string_from_jni!(tx_slate_id, env);
+        to_json(&tx_get(&wallet, None, refresh_from_node, &tx_slate_id)?)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_txsGet(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
     minimum_confirmations: jlong,
     refresh_from_node: bool,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    unwrap_to_jni!(
-        env,
-        txs_get(&wallet, minimum_confirmations as u64, refresh_from_node)
-    )
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet);
+        to_json(&txs_get(
+            &wallet,
+            None,
+            minimum_confirmations as u64,
+            refresh_from_node,
+        )?)
+    })
 }
 
 #[no_mangle]
@@ -132,54 +152,54 @@ pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_openWallet(
     config_str: JString,
     password: JString,
 ) -> jlong {
-    get_string_from_jni!(config_str, env);
-    get_string_from_jni!(password, env);
-    match open_wallet(&config_str, &password) {
-        Ok(res) => Box::into_raw(Box::new(res)) as jlong,
-        Err(e) => {
-            let _ = env.throw(serde_json::to_string(&format!("{}", e)).unwrap());
-            0 as jlong
-        }
-    }
+    unwrap_handle_to_jni!(env, {
+        get_string_from_jni!(config_str, env);
+        get_string_from_jni!(password, env);
+        OpenedWallet::open(&config_str, &password).map(|opened| WALLETS.insert(opened))
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_closeWallet(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    let result = close_wallet(&wallet);
-    if result.is_ok() {
-        Box::from_raw(wallet);
-    };
-    unwrap_to_jni!(env, result)
+    unwrap_to_jni!(env, {
+        OpenedWallet::close(wallet_handle as Handle)?;
+        to_json(&EmptyResponse::new())
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_walletPmmrRange(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    unwrap_to_jni!(env, wallet_pmmr_range(&wallet))
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet);
+        to_json(&wallet_pmmr_range(&wallet)?)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_walletScanOutputs(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
     last_retrieved_index: jlong,
     highest_index: jlong,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    unwrap_to_jni!(
-        env,
-        wallet_scan_outputs(&wallet, last_retrieved_index as u64, highest_index as u64)
-    )
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet);
+        to_json(&wallet_scan_outputs(
+            &wallet,
+            None,
+            last_retrieved_index as u64,
+            highest_index as u64,
+        )?)
+    })
 }
 
 #[no_mangle]
@@ -189,118 +209,139 @@ pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_walletPhrase(
     wallet_dir: JString,
     password: JString,
 ) -> jstring {
-    get_string_from_jni!(wallet_dir, env);
-    get_string_from_jni!(password, env);
-    unwrap_to_jni!(env, wallet_phrase(&wallet_dir, &password))
+    unwrap_to_jni!(env, {
+        get_string_from_jni!(wallet_dir, env);
+        get_string_from_jni!(password, env);
+        to_json(&wallet_phrase(&wallet_dir, &password)?)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_txStrategies(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
     amount: jlong,
     minimum_confirmations: jlong,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    unwrap_to_jni!(
-        env,
-        tx_strategies(&wallet, amount as u64, minimum_confirmations as u64)
-    )
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet, config);
+        to_json(&tx_strategies(
+            &wallet,
+            &config,
+            amount as u64,
+            minimum_confirmations as u64,
+        )?)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_txCreate(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
     amount: jlong,
     minimum_confirmations: jlong,
     selection_strategy_is_use_all: bool,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    unwrap_to_jni!(
-        env,
-        tx_create(
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet, config);
+        to_json(&tx_create(
             &wallet,
+            None,
+            &config,
             amount as u64,
             minimum_confirmations as u64,
-            selection_strategy_is_use_all
-        )
-    )
+            selection_strategy_is_use_all,
+            None,
+            None,
+        )?)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_txCancel(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
     id: jlong,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    unwrap_to_jni!(env, tx_cancel(&wallet, id as u32,))
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet);
+        tx_cancel(&wallet, None, id as u32)?;
+        to_json(&EmptyResponse::new())
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_txReceive(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
     account: JString,
     slate_armored: JString,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    get_string_from_jni!(slate_armored, env);
-    get_string_from_jni!(account, env);
-    unwrap_to_jni!(env, tx_receive(&wallet, &account, &slate_armored))
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet);
+        get_string_from_jni!(slate_armored, env);
+        get_string_from_jni!(account, env);
+        to_json(&tx_receive(&wallet, None, &account, &slate_armored)?)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_txFinalize(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
     slate_armored: JString,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    get_string_from_jni!(slate_armored, env);
-    unwrap_to_jni!(env, tx_finalize(&wallet, &slate_armored))
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet);
+        get_string_from_jni!(slate_armored, env);
+        to_json(&tx_finalize(&wallet, None, &slate_armored)?)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_txSendAddress(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
     amount: jlong,
     minimum_confirmations: jlong,
     selection_strategy_is_use_all: bool,
     address: JString,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    get_string_from_jni!(address, env);
-    unwrap_to_jni!(
-        env,
-        tx_send_address(
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet, config);
+        get_string_from_jni!(address, env);
+        to_json(&tx_send_address(
             &wallet,
+            None,
+            &config,
             &address,
             amount as u64,
             minimum_confirmations as u64,
-            selection_strategy_is_use_all
-        )
-    )
+            selection_strategy_is_use_all,
+            None,
+        )?)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_txPost(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
     tx_slate_id: JString,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    get_string_from_jni!(tx_slate_id, env);
-    unwrap_to_jni!(env, tx_post(&wallet, &tx_slate_id))
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet);
+        get_string_from_jni!(tx_slate_id, env);
+        tx_post(&wallet, None, &tx_slate_id)?;
+        to_json(&EmptyResponse::new())
+    })
 }
 
 #[no_mangle]
@@ -309,37 +350,38 @@ pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_slatepackDecode(
     _: JClass,
     slatepack: JString,
 ) -> jstring {
-    get_string_from_jni!(slatepack, env);
-    unwrap_to_jni!(env, slatepack_decode(&slatepack))
+    unwrap_to_jni!(env, {
+        get_string_from_jni!(slatepack, env);
+        to_json(&slatepack_decode(&slatepack)?)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_getGrinAddress(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: jlong,
+    wallet_handle: jlong,
 ) -> jstring {
-    ensure_wallet!(wallet_ptr, wallet, env);
-    unwrap_to_jni!(env, get_grin_address(&wallet))
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet);
+        to_json(&get_grin_address(&wallet, None)?)
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_startListenWithHttp(
     env: JNIEnv,
     _: JClass,
-    wallet_ptr: usize,
+    wallet_handle: jlong,
     api_listen_addr: JString,
 ) -> jlong {
-    if (wallet_ptr as *mut Wallet).as_mut().is_none() {
-        let _ = env.throw(serde_json::to_string(&format!("Wallet is NULL")).unwrap());
-    }
-    let wallet = (wallet_ptr as *mut Wallet).as_mut().unwrap();
-    get_string_from_jni!(api_listen_addr, env);
-    let mut apis = ApiServer::new();
-    if let Err(e) = start_listen_with_http(&wallet, &api_listen_addr, &mut apis) {
-        let _ = env.throw(serde_json::to_string(&format!("{}", e)).unwrap());
-    }
-    Box::into_raw(Box::new(apis)) as jlong
+    unwrap_handle_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet);
+        get_string_from_jni!(api_listen_addr, env);
+        let mut apis = ApiServer::new();
+        start_listen_with_http(&wallet, &api_listen_addr, &mut apis)?;
+        Ok(API_SERVERS.insert(Mutex::new(apis)))
+    })
 }
 
 #[no_mangle]
@@ -348,31 +390,24 @@ pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_stopListenWithHttp(
     _: JClass,
     api_server: jlong,
 ) -> jstring {
-    if let Some(apis) = (api_server as *mut ApiServer).as_mut() {
-        apis.stop();
-        Box::from_raw(api_server as *mut ApiServer);
-        env.new_string("").unwrap().into_inner()
-    } else {
-        let _ = env.throw(serde_json::to_string(&format!("Api server is NULL")).unwrap());
-        env.new_string("").unwrap().into_inner()
-    }
+    unwrap_to_jni!(env, {
+        let apis = API_SERVERS.remove(api_server as Handle)?;
+        apis.lock().stop();
+        to_json(&EmptyResponse::new())
+    })
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn Java_app_ironbelly_GrinBridge_createTorConfig(
     env: JNIEnv,
     _: JClass,
-    opened_wallet: jlong,
+    wallet_handle: jlong,
     listen_addr: JString,
 ) -> jstring {
-    let listen_addr: String = env
-        .get_string(listen_addr)
-        .expect("Invalid listen addr")
-        .into();
-    if let Some(wallet) = (opened_wallet as *mut Wallet).as_mut() {
-        unwrap_to_jni!(env, create_tor_config(&wallet, &listen_addr))
-    } else {
-        let _ = env.throw(serde_json::to_string(&format!("Opened wallet is empty")).unwrap());
-        env.new_string("").unwrap().into_inner()
-    }
+    unwrap_to_jni!(env, {
+        ensure_wallet!(wallet_handle, wallet, config);
+        get_string_from_jni!(listen_addr, env);
+        create_tor_config(&wallet, None, &config, &listen_addr)?;
+        to_json(&EmptyResponse::new())
+    })
 }
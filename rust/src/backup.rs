@@ -0,0 +1,425 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// Full wallet backups. Restoring from the recovery phrase only brings back
+// outputs, so the backup carries the whole data dir: the database with the
+// tx log and private contexts, saved transactions and the tor keys.
+//
+// Archive layout: MAGIC, version byte, salt, nonce, tag, then a tar of the
+// wallet directories encrypted with AES-256-GCM. The key is derived from the
+// wallet password, and the magic and version are authenticated too. The tar
+// is streamed through the cipher in chunks, so the size of a wallet isn't
+// limited by memory, and the tag is filled in once everything is written.
+use crate::errors::{Error, ErrorKind};
+use crate::{wallet_exists_in, Config, ROLLBACK_SEED_FILE, SEED_FILE, WALLET_DIRS};
+use failure::ResultExt;
+use grin_util::ZeroingString;
+use grin_wallet_config::GRIN_WALLET_DIR;
+use grin_wallet_impls::WalletSeed;
+use openssl::hash::MessageDigest;
+use openssl::pkcs5::pbkdf2_hmac;
+use openssl::rand::rand_bytes;
+use openssl::symm::{Cipher, Crypter, Mode};
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::{Component, Path};
+
+const MAGIC: &[u8; 8] = b"IBBACKUP";
+/// Version of the archive format, bumped on any incompatible change
+pub const BACKUP_VERSION: u8 = 1;
+const SALT_LEN: usize = 16;
+const NONCE_LEN: usize = 12;
+const TAG_LEN: usize = 16;
+const HEADER_LEN: usize = MAGIC.len() + 1 + SALT_LEN + NONCE_LEN + TAG_LEN;
+const KDF_ITERATIONS: usize = 100_000;
+/// Size of the chunks the archive is encrypted and decrypted in
+const CHUNK_LEN: usize = 64 * 1024;
+/// Files which are recreated by the wallet and must not be restored
+const SKIPPED_FILES: [&str; 2] = ["lock.mdb", ROLLBACK_SEED_FILE];
+/// Directory inside the data dir an archive is unpacked to before it is
+/// moved in place
+const RESTORE_DIR: &str = "restore.tmp";
+
+/// Write an encrypted archive of the wallet the config points to. The
+/// password has to be the wallet password, it encrypts the archive as well.
+pub fn wallet_backup_export(
+    config_json: &str,
+    password: &str,
+    backup_file: &str,
+) -> Result<(), Error> {
+    let config = Config::from_str(config_json)?;
+    let data_dir = config.data_dir();
+    if !wallet_exists_in(&data_dir) {
+        return Err(ErrorKind::WalletDoesntExist().into());
+    }
+    // An archive with a seed its password can't decrypt couldn't be restored
+    WalletSeed::from_file(
+        &format!("{}/{}", data_dir, GRIN_WALLET_DIR),
+        ZeroingString::from(password),
+    )?;
+
+    let file = File::create(backup_file).context(ErrorKind::IO)?;
+    let result = (|| -> Result<(), Error> {
+        let writer = EncryptingWriter::new(BufWriter::new(file), password)?;
+        let mut builder = tar::Builder::new(writer);
+        for dir in &WALLET_DIRS {
+            let path = Path::new(&data_dir).join(dir);
+            if path.is_dir() {
+                append_dir(&mut builder, &path, Path::new(dir))?;
+            }
+        }
+        let writer = builder.into_inner().context(ErrorKind::IO)?;
+        writer.finish()?.flush().context(ErrorKind::IO)?;
+        Ok(())
+    })();
+    // Don't leave a truncated archive behind
+    if result.is_err() {
+        let _ = fs::remove_file(backup_file);
+    }
+    result
+}
+
+/// Restore an archive into the data dir of the config, which must not hold
+/// a wallet yet
+pub fn wallet_backup_import(
+    config_json: &str,
+    password: &str,
+    backup_file: &str,
+) -> Result<(), Error> {
+    let config = Config::from_str(config_json)?;
+    let data_dir = config.data_dir();
+    if wallet_exists_in(&data_dir) {
+        return Err(ErrorKind::WalletAlreadyExists(config.wallet_name().to_owned()).into());
+    }
+    let file = File::open(backup_file).context(ErrorKind::IO)?;
+    let mut reader = DecryptingReader::new(BufReader::new(file), password)?;
+
+    let restore_dir = Path::new(&data_dir).join(RESTORE_DIR);
+    if restore_dir.exists() {
+        fs::remove_dir_all(&restore_dir).context(ErrorKind::IO)?;
+    }
+    // The archive is only authenticated once it has been read to the end, so
+    // nothing is moved out of the restore dir before
+    let unpacked = unpack(&mut reader, &restore_dir, password);
+    let result = reader.finish().and(unpacked).and_then(|_| {
+        for dir in &WALLET_DIRS {
+            let (from, to) = (restore_dir.join(dir), Path::new(&data_dir).join(dir));
+            // Leftovers of a deleted wallet or an interrupted restore
+            if to.exists() {
+                fs::remove_dir_all(&to).context(ErrorKind::IO)?;
+            }
+            if from.exists() {
+                fs::rename(&from, &to).context(ErrorKind::IO)?;
+            }
+        }
+        Ok(())
+    });
+    if restore_dir.exists() {
+        fs::remove_dir_all(&restore_dir).context(ErrorKind::IO)?;
+    }
+    result
+}
+
+fn append_dir<W: Write>(
+    builder: &mut tar::Builder<W>,
+    dir: &Path,
+    name: &Path,
+) -> Result<(), Error> {
+    for entry in fs::read_dir(dir).context(ErrorKind::IO)? {
+        let entry = entry.context(ErrorKind::IO)?;
+        let (path, file_name) = (entry.path(), entry.file_name());
+        if path.is_dir() {
+            append_dir(builder, &path, &name.join(&file_name))?;
+        } else if !SKIPPED_FILES.iter().any(|skipped| file_name == *skipped) {
+            builder
+                .append_path_with_name(&path, name.join(&file_name))
+                .context(ErrorKind::IO)?;
+        }
+    }
+    Ok(())
+}
+
+/// Unpack into `restore_dir` and check the result is a wallet which opens
+/// with the password
+fn unpack<R: Read>(archive: R, restore_dir: &Path, password: &str) -> Result<(), Error> {
+    fs::create_dir_all(restore_dir).context(ErrorKind::IO)?;
+    let mut archive = tar::Archive::new(archive);
+    for entry in archive.entries().context(ErrorKind::IO)? {
+        let mut entry = entry.context(ErrorKind::IO)?;
+        let path = entry.path().context(ErrorKind::IO)?.into_owned();
+        let top_level = match path.components().next() {
+            Some(Component::Normal(dir)) => dir.to_string_lossy().into_owned(),
+            _ => String::new(),
+        };
+        if !WALLET_DIRS.contains(&top_level.as_str()) {
+            return Err(invalid(format!("unexpected entry {}", path.display())));
+        }
+        if !entry.unpack_in(restore_dir).context(ErrorKind::IO)? {
+            return Err(invalid(format!("entry {} escapes", path.display())));
+        }
+    }
+    let seed_dir = restore_dir.join(GRIN_WALLET_DIR);
+    if !seed_dir.join(SEED_FILE).is_file() {
+        return Err(invalid("no wallet seed".to_owned()));
+    }
+    WalletSeed::from_file(&seed_dir.to_string_lossy(), ZeroingString::from(password))?;
+    Ok(())
+}
+
+/// Encrypts everything written to it, writing the header up front
+struct EncryptingWriter<W: Write> {
+    inner: W,
+    crypter: Crypter,
+    buf: Vec<u8>,
+}
+
+impl<W: Write + Seek> EncryptingWriter<W> {
+    fn new(mut inner: W, password: &str) -> Result<Self, Error> {
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce = [0u8; NONCE_LEN];
+        rand_bytes(&mut salt).map_err(crypto_error)?;
+        rand_bytes(&mut nonce).map_err(crypto_error)?;
+        let key = derive_key(password, &salt)?;
+        let aad = aad(BACKUP_VERSION);
+        let mut crypter = Crypter::new(Cipher::aes_256_gcm(), Mode::Encrypt, &key, Some(&nonce))
+            .map_err(crypto_error)?;
+        crypter.aad_update(&aad).map_err(crypto_error)?;
+        // The tag is only known at the end, see `finish`
+        for part in &[&aad[..], &salt[..], &nonce[..], &[0u8; TAG_LEN][..]] {
+            inner.write_all(part).context(ErrorKind::IO)?;
+        }
+        Ok(EncryptingWriter {
+            inner,
+            crypter,
+            buf: vec![],
+        })
+    }
+
+    /// Complete the encryption and put the tag into the header
+    fn finish(mut self) -> Result<W, Error> {
+        self.buf.resize(Cipher::aes_256_gcm().block_size(), 0);
+        let len = self.crypter.finalize(&mut self.buf).map_err(crypto_error)?;
+        self.inner
+            .write_all(&self.buf[..len])
+            .context(ErrorKind::IO)?;
+        let mut tag = [0u8; TAG_LEN];
+        self.crypter.get_tag(&mut tag).map_err(crypto_error)?;
+        self.inner
+            .seek(SeekFrom::Start((HEADER_LEN - TAG_LEN) as u64))
+            .context(ErrorKind::IO)?;
+        self.inner.write_all(&tag).context(ErrorKind::IO)?;
+        Ok(self.inner)
+    }
+}
+
+impl<W: Write> Write for EncryptingWriter<W> {
+    fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+        let data = &data[..data.len().min(CHUNK_LEN)];
+        self.buf
+            .resize(data.len() + Cipher::aes_256_gcm().block_size(), 0);
+        let len = self
+            .crypter
+            .update(data, &mut self.buf)
+            .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?;
+        self.inner.write_all(&self.buf[..len])?;
+        Ok(data.len())
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+/// Decrypts an archive after checking its header. What it reads isn't
+/// authenticated before `finish` succeeded.
+struct DecryptingReader<R: Read> {
+    inner: R,
+    crypter: Crypter,
+    chunk: Vec<u8>,
+    plain: Vec<u8>,
+    pos: usize,
+    done: bool,
+    /// The tag didn't match, because of a wrong password or tampering
+    auth_failed: bool,
+}
+
+impl<R: Read> DecryptingReader<R> {
+    fn new(mut inner: R, password: &str) -> Result<Self, Error> {
+        let mut header = [0u8; HEADER_LEN];
+        match inner.read_exact(&mut header) {
+            Err(e) if e.kind() == io::ErrorKind::UnexpectedEof => {
+                return Err(invalid("not a wallet backup".to_owned()));
+            }
+            read => read.context(ErrorKind::IO)?,
+        }
+        if &header[..MAGIC.len()] != MAGIC {
+            return Err(invalid("not a wallet backup".to_owned()));
+        }
+        let version = header[MAGIC.len()];
+        if version != BACKUP_VERSION {
+            return Err(invalid(format!("version {} is not supported", version)));
+        }
+        let (aad, rest) = header.split_at(MAGIC.len() + 1);
+        let (salt, rest) = rest.split_at(SALT_LEN);
+        let (nonce, tag) = rest.split_at(NONCE_LEN);
+        let key = derive_key(password, salt)?;
+        let mut crypter = Crypter::new(Cipher::aes_256_gcm(), Mode::Decrypt, &key, Some(nonce))
+            .map_err(crypto_error)?;
+        crypter.aad_update(aad).map_err(crypto_error)?;
+        crypter.set_tag(tag).map_err(crypto_error)?;
+        Ok(DecryptingReader {
+            inner,
+            crypter,
+            chunk: vec![0u8; CHUNK_LEN],
+            plain: vec![],
+            pos: 0,
+            done: false,
+            auth_failed: false,
+        })
+    }
+
+    /// Read the rest of the archive, which authenticates it. Authentication
+    /// fails for a wrong password and for a tampered archive alike, a wrong
+    /// password is by far the more likely. Whatever it decrypted to may
+    /// have failed to unpack before, so this error takes precedence.
+    fn finish(mut self) -> Result<(), Error> {
+        let drained = io::copy(&mut self, &mut io::sink());
+        if self.auth_failed {
+            return Err(ErrorKind::WrongPassword().into());
+        }
+        drained.context(ErrorKind::IO)?;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for DecryptingReader<R> {
+    fn read(&mut self, out: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.plain.len() {
+            if self.done {
+                return Ok(0);
+            }
+            let read = self.inner.read(&mut self.chunk)?;
+            self.plain
+                .resize(read + Cipher::aes_256_gcm().block_size(), 0);
+            let len = if read == 0 {
+                self.done = true;
+                match self.crypter.finalize(&mut self.plain) {
+                    Ok(len) => len,
+                    Err(e) => {
+                        self.auth_failed = true;
+                        return Err(io::Error::new(io::ErrorKind::InvalidData, e));
+                    }
+                }
+            } else {
+                self.crypter
+                    .update(&self.chunk[..read], &mut self.plain)
+                    .map_err(|e| io::Error::new(io::ErrorKind::Other, e))?
+            };
+            self.plain.truncate(len);
+            self.pos = 0;
+        }
+        let len = out.len().min(self.plain.len() - self.pos);
+        out[..len].copy_from_slice(&self.plain[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+fn aad(version: u8) -> Vec<u8> {
+    let mut aad = MAGIC.to_vec();
+    aad.push(version);
+    aad
+}
+
+fn derive_key(password: &str, salt: &[u8]) -> Result<[u8; 32], Error> {
+    let mut key = [0u8; 32];
+    pbkdf2_hmac(
+        password.as_bytes(),
+        salt,
+        KDF_ITERATIONS,
+        MessageDigest::sha512(),
+        &mut key,
+    )
+    .map_err(crypto_error)?;
+    Ok(key)
+}
+
+fn invalid(message: String) -> Error {
+    ErrorKind::InvalidBackup(message).into()
+}
+
+fn crypto_error(e: openssl::error::ErrorStack) -> Error {
+    ErrorKind::GenericError(format!("Backup encryption failed: {}", e)).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    fn encrypt(data: &[u8], password: &str) -> Vec<u8> {
+        let mut writer = EncryptingWriter::new(Cursor::new(vec![]), password).unwrap();
+        writer.write_all(data).unwrap();
+        writer.finish().unwrap().into_inner()
+    }
+
+    fn decrypt(data: &[u8], password: &str) -> Result<Vec<u8>, Error> {
+        let mut reader = DecryptingReader::new(data, password)?;
+        let mut plain = vec![];
+        let read = reader.read_to_end(&mut plain);
+        reader.finish()?;
+        read.context(ErrorKind::IO)?;
+        Ok(plain)
+    }
+
+    #[test]
+    fn encryption_round_trip() {
+        let encrypted = encrypt(b"wallet data", "password");
+        assert_eq!(decrypt(&encrypted, "password").unwrap(), b"wallet data");
+        assert_eq!(
+            decrypt(&encrypted, "wrong").unwrap_err().kind(),
+            ErrorKind::WrongPassword()
+        );
+    }
+
+    #[test]
+    fn archives_are_encrypted_in_chunks() {
+        let data: Vec<u8> = (0..CHUNK_LEN * 3 + 100).map(|i| i as u8).collect();
+        let mut encrypted = encrypt(&data, "password");
+        assert_eq!(encrypted.len(), HEADER_LEN + data.len());
+        assert_eq!(decrypt(&encrypted, "password").unwrap(), data);
+
+        // Any change of the data fails the authentication at the end
+        encrypted[HEADER_LEN + CHUNK_LEN * 2] ^= 1;
+        assert_eq!(
+            decrypt(&encrypted, "password").unwrap_err().kind(),
+            ErrorKind::WrongPassword()
+        );
+    }
+
+    #[test]
+    fn header_is_checked() {
+        let mut encrypted = encrypt(b"wallet data", "password");
+        match decrypt(b"IBBACKUP", "password").unwrap_err().kind() {
+            ErrorKind::InvalidBackup(_) => {}
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+        encrypted[MAGIC.len()] = BACKUP_VERSION + 1;
+        match decrypt(&encrypted, "password").unwrap_err().kind() {
+            ErrorKind::InvalidBackup(m) => assert!(m.contains("not supported")),
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+}
@@ -0,0 +1,501 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// Command line frontend for the wallet core, mainly for testing wallet
+// flows on desktop without building the mobile app.
+#[macro_use]
+extern crate clap;
+#[macro_use]
+extern crate prettytable;
+
+use clap::ArgMatches;
+use grin_core::core::{amount_from_hr_string, amount_to_hr_string};
+use grin_wallet_libwallet::{TxLogEntry, WalletInfo};
+use prettytable::Table;
+use simplelog::{LevelFilter, TermLogger, TerminalMode};
+use std::fs;
+use std::path::Path;
+use std::process::exit;
+use std::sync::mpsc::channel;
+use wallet::{Config, Error, ErrorKind, Wallet};
+
+fn main() {
+    let yml = load_yaml!("ironbelly-cli.yml");
+    let args = clap::App::from_yaml(yml)
+        .version(crate_version!())
+        .get_matches();
+
+    let _ = TermLogger::init(
+        LevelFilter::Error,
+        simplelog::Config::default(),
+        TerminalMode::Mixed,
+    );
+
+    if let Err(e) = run(&args) {
+        eprintln!("Error: {}", e);
+        exit(1);
+    }
+}
+
+fn run(args: &ArgMatches) -> Result<(), Error> {
+    let config = config_from_args(args)?;
+    let config_json =
+        serde_json::to_string(&config).map_err(|e| ErrorKind::GenericError(e.to_string()))?;
+
+    match args.subcommand() {
+        ("init", Some(m)) => {
+            let password = password(args, true)?;
+            let phrase = match m.value_of("phrase") {
+                Some(phrase) => phrase.to_owned(),
+                None => {
+                    let phrase = wallet::seed_new(32)?.phrase;
+                    println!("Your recovery phrase is:\n\n{}\n", phrase);
+                    println!("Please back it up, it is the only way to restore your funds.");
+                    phrase
+                }
+            };
+            wallet::wallet_init(&config_json, &phrase, &password)?;
+            println!(
+                "Wallet {} created in {}",
+                config.wallet_name(),
+                config.data_dir()
+            );
+        }
+        ("wallets", Some(_)) => {
+            for name in wallet::wallets_list(&config.wallet_dir)?.wallets {
+                println!("{}", name);
+            }
+        }
+        ("delete", Some(_)) => {
+            let password = password(args, false)?;
+            wallet::wallet_delete(&config_json, &password)?;
+            println!("Wallet {} deleted", config.wallet_name());
+        }
+        ("change_password", Some(m)) => {
+            let password = password(args, false)?;
+            let new_password = match m.value_of("new_pass") {
+                Some(new_password) => new_password.to_owned(),
+                None => prompt_password("New password: ", true)?,
+            };
+            wallet::wallet_change_password(&config_json, &password, &new_password)?;
+            println!("Password of wallet {} changed", config.wallet_name());
+        }
+        ("backup", Some(m)) => {
+            let password = password(args, false)?;
+            let file = m.value_of("file").unwrap();
+            wallet::wallet_backup_export(&config_json, &password, file)?;
+            println!("Wallet {} backed up to {}", config.wallet_name(), file);
+        }
+        ("restore_backup", Some(m)) => {
+            let password = password(args, false)?;
+            wallet::wallet_backup_import(&config_json, &password, m.value_of("file").unwrap())?;
+            println!(
+                "Wallet {} restored in {}",
+                config.wallet_name(),
+                config.data_dir()
+            );
+        }
+        ("phrase", Some(_)) => {
+            let password = password(args, false)?;
+            println!(
+                "{}",
+                wallet::wallet_phrase(&config.data_dir(), &password)?.phrase
+            );
+        }
+        ("decode", Some(m)) => {
+            let slatepack = slatepack_input(m)?;
+            let slate = match wallet::slatepack_decode(&slatepack) {
+                // Only the wallet it is encrypted to can read it
+                Err(e) if e.kind().code() == "slatepack_decryption" => {
+                    let password = password(args, false)?;
+                    let wallet = wallet::open_wallet(&config_json, &password)?;
+                    let result = wallet::slatepack_decode_with_wallet(&wallet, None, &slatepack);
+                    wallet::close_wallet(&wallet)?;
+                    result?.slate
+                }
+                result => result?.slate,
+            };
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&slate)
+                    .map_err(|e| ErrorKind::GenericError(e.to_string()))?
+            );
+        }
+        (command, Some(m)) => {
+            let password = password(args, false)?;
+            let wallet = wallet::open_wallet(&config_json, &password)?;
+            let result = run_with_wallet(&wallet, &config, command, m);
+            wallet::close_wallet(&wallet)?;
+            result?;
+        }
+        _ => {
+            println!("{}", args.usage());
+        }
+    }
+    Ok(())
+}
+
+fn run_with_wallet(
+    wallet: &Wallet,
+    config: &Config,
+    command: &str,
+    args: &ArgMatches,
+) -> Result<(), Error> {
+    match command {
+        "address" => {
+            println!("{}", wallet::get_grin_address(wallet, None)?.address);
+        }
+        "txs" => {
+            let min_conf = parse_u64(args, "min_conf")?;
+            let resp = wallet::txs_get(wallet, None, min_conf, !args.is_present("no_refresh"))?;
+            if !resp.refreshed {
+                println!("WARNING: wallet state was not refreshed from the node");
+            }
+            print_info(&resp.wallet_info);
+            print_txs(&resp.txs);
+        }
+        "accounts" => {
+            let min_conf = parse_u64(args, "min_conf")?;
+            let mut table = Table::new();
+            table.set_titles(row![bMG->"Label", bMG->"Path", bMG->"Total", bMG->"Spendable"]);
+            for account in wallet::accounts_list(wallet, None, min_conf)?.accounts {
+                table.add_row(row![
+                    account.label,
+                    account.path,
+                    amount_to_hr_string(account.info.total, false),
+                    amount_to_hr_string(account.info.amount_currently_spendable, false),
+                ]);
+            }
+            table.printstd();
+        }
+        "account_create" => {
+            let account = wallet::account_create(wallet, None, args.value_of("label").unwrap())?;
+            println!(
+                "Account {} created at {}",
+                account.account.label, account.account.path
+            );
+        }
+        "account_rename" => {
+            let account = wallet::account_rename(
+                wallet,
+                None,
+                args.value_of("label").unwrap(),
+                args.value_of("new_label").unwrap(),
+            )?;
+            println!("Account {} renamed", account.account.label);
+        }
+        "tx" => {
+            let resp = wallet::tx_get(wallet, None, true, args.value_of("slate_id").unwrap())?;
+            print_txs(&resp.txs);
+        }
+        "strategies" => {
+            let amount = parse_amount(args)?;
+            let min_conf = parse_u64(args, "min_conf")?;
+            let mut table = Table::new();
+            table.set_titles(row![bMG->"Use all outputs", bMG->"Total", bMG->"Fee"]);
+            for s in wallet::tx_strategies(wallet, config, amount, min_conf)?.strategies {
+                table.add_row(row![
+                    s.selection_strategy_is_use_all,
+                    amount_to_hr_string(s.total, false),
+                    amount_to_hr_string(s.fee, false),
+                ]);
+            }
+            table.printstd();
+        }
+        "fee" => {
+            let amount = parse_amount(args)?;
+            let min_conf = parse_u64(args, "min_conf")?;
+            let change_outputs = match args.value_of("change_outputs") {
+                Some(_) => Some(parse_u64(args, "change_outputs")? as usize),
+                None => None,
+            };
+            let resp = wallet::tx_fee_estimate(
+                wallet,
+                config,
+                amount,
+                min_conf,
+                !args.is_present("smallest"),
+                parse_fee_base(args)?,
+                change_outputs,
+            )?;
+            let mut table = Table::new();
+            table.add_row(row![bFG->"Fee", amount_to_hr_string(resp.fee, false)]);
+            table.add_row(row![bFY->"Fee base", resp.fee_base]);
+            table.add_row(row![bFY->"Weight", resp.weight]);
+            table.add_row(row![bFY->"Inputs", resp.inputs]);
+            table.add_row(row![bFY->"Outputs", resp.outputs]);
+            table.add_row(row![bFY->"Inputs total", amount_to_hr_string(resp.total, false)]);
+            table.printstd();
+        }
+        "send" => {
+            let amount = parse_amount(args)?;
+            let min_conf = parse_u64(args, "min_conf")?;
+            let fee_base = parse_fee_base(args)?;
+            let resp = wallet::tx_create(
+                wallet,
+                None,
+                config,
+                amount,
+                min_conf,
+                !args.is_present("smallest"),
+                args.value_of("recipient"),
+                fee_base,
+            )?;
+            print_txs(&resp.txs);
+            println!("\n{}\n", resp.slatepack);
+        }
+        "transfer" => {
+            let amount = parse_amount(args)?;
+            let min_conf = parse_u64(args, "min_conf")?;
+            let resp = wallet::tx_transfer_between_accounts(
+                wallet,
+                None,
+                config,
+                args.value_of("from").unwrap(),
+                args.value_of("to").unwrap(),
+                amount,
+                min_conf,
+                !args.is_present("smallest"),
+            )?;
+            print_txs(&resp.txs);
+        }
+        "receive" => {
+            let account = config
+                .account
+                .clone()
+                .unwrap_or_else(|| wallet::DEFAULT_ACCOUNT.to_owned());
+            let resp = wallet::tx_receive(wallet, None, &account, &slatepack_input(args)?)?;
+            print_txs(&resp.txs);
+            if let Some(address) = &resp.counterparty_address {
+                println!("\nSender: {}", address);
+            }
+            println!(
+                "\nReturn this slatepack to the sender:\n\n{}\n",
+                resp.slatepack
+            );
+        }
+        "finalize" => {
+            let resp = wallet::tx_finalize(wallet, None, &slatepack_input(args)?)?;
+            print_txs(&resp.txs);
+            if let Some(address) = &resp.counterparty_address {
+                println!("\nRecipient: {}", address);
+            }
+        }
+        "invoice" => {
+            let account = config
+                .account
+                .clone()
+                .unwrap_or_else(|| wallet::DEFAULT_ACCOUNT.to_owned());
+            let resp = wallet::tx_issue_invoice(wallet, None, parse_amount(args)?, &account)?;
+            print_txs(&resp.txs);
+            println!("\n{}\n", resp.slatepack);
+        }
+        "pay" => {
+            let min_conf = parse_u64(args, "min_conf")?;
+            let resp = wallet::tx_process_invoice(
+                wallet,
+                None,
+                config,
+                &slatepack_input(args)?,
+                min_conf,
+                !args.is_present("smallest"),
+            )?;
+            print_txs(&resp.txs);
+            println!(
+                "\nReturn this slatepack to the issuer:\n\n{}\n",
+                resp.slatepack
+            );
+        }
+        "post" => {
+            wallet::tx_post(wallet, None, args.value_of("slate_id").unwrap())?;
+            println!("Transaction posted");
+        }
+        "proof" => {
+            let resp =
+                wallet::payment_proof_export(wallet, None, args.value_of("slate_id").unwrap())?;
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&resp.proof)
+                    .map_err(|e| ErrorKind::GenericError(e.to_string()))?
+            );
+        }
+        "verify_proof" => {
+            let resp = wallet::payment_proof_verify(wallet, None, &slatepack_input(args)?)?;
+            println!("Payment proof is valid");
+            if resp.is_sender {
+                println!("This wallet is the sender");
+            }
+            if resp.is_recipient {
+                println!("This wallet is the recipient");
+            }
+        }
+        "cancel" => {
+            let id = parse_u64(args, "id")?;
+            wallet::tx_cancel(wallet, None, id as u32)?;
+            println!("Transaction {} cancelled", id);
+        }
+        "scan" => {
+            let range = wallet::wallet_pmmr_range(wallet)?;
+            let (mut last_retrieved_index, highest_index) =
+                (range.lowest_index, range.highest_index);
+            while last_retrieved_index < highest_index {
+                last_retrieved_index =
+                    wallet::wallet_scan_outputs(wallet, None, last_retrieved_index, highest_index)?
+                        .last_retrieved_index;
+                println!("Scanned {}/{}", last_retrieved_index, highest_index);
+            }
+            println!("Scan done");
+        }
+        "listen" => {
+            let listen_addr = format!("127.0.0.1:{}", config.api_listen_port);
+            let mut apis = grin_api::ApiServer::new();
+            wallet::start_listen_with_http(wallet, &listen_addr, &mut apis)?;
+            println!("Listening on {}, press Ctrl-C to stop", listen_addr);
+
+            let (tx, rx) = channel();
+            ctrlc::set_handler(move || {
+                let _ = tx.send(());
+            })
+            .map_err(|e| ErrorKind::GenericError(e.to_string()))?;
+            let _ = rx.recv();
+            apis.stop();
+        }
+        _ => unreachable!("unknown subcommand {}", command),
+    }
+    Ok(())
+}
+
+fn config_from_args(args: &ArgMatches) -> Result<Config, Error> {
+    let mut config = serde_json::json!({
+        "wallet_dir": args.value_of("wallet_dir").unwrap(),
+        "wallet_name": args.value_of("name").unwrap(),
+        "check_node_api_http_addr": args.value_of("node").unwrap(),
+        "chain": args.value_of("chain").unwrap(),
+        "account": args.value_of("account"),
+    });
+    if args.is_present("api_listen_port") {
+        config["api_listen_port"] = parse_u64(args, "api_listen_port")?.into();
+    }
+    if let Some(addr) = args.value_of("tor_socks_addr") {
+        config["tor_socks_addr"] = addr.into();
+    }
+    Config::from_str(&config.to_string())
+}
+
+fn password(args: &ArgMatches, confirm: bool) -> Result<String, Error> {
+    if let Some(password) = args.value_of("pass") {
+        return Ok(password.to_owned());
+    }
+    prompt_password("Password: ", confirm)
+}
+
+fn prompt_password(prompt: &str, confirm: bool) -> Result<String, Error> {
+    let password = rpassword::prompt_password_stdout(prompt)
+        .map_err(|e| ErrorKind::GenericError(format!("Could not read password: {}", e)))?;
+    if confirm {
+        let confirmation = rpassword::prompt_password_stdout("Confirm password: ")
+            .map_err(|e| ErrorKind::GenericError(format!("Could not read password: {}", e)))?;
+        if password != confirmation {
+            return Err(ErrorKind::GenericError("Passwords do not match".to_owned()).into());
+        }
+    }
+    Ok(password)
+}
+
+/// Slatepack given on the command line, or read from a file if the argument is a path
+fn slatepack_input(args: &ArgMatches) -> Result<String, Error> {
+    let input = args.value_of("input").unwrap();
+    if Path::new(input).is_file() {
+        let slatepack = fs::read_to_string(input)
+            .map_err(|e| ErrorKind::GenericError(format!("Could not read {}: {}", input, e)))?;
+        Ok(slatepack.trim().to_owned())
+    } else {
+        Ok(input.to_owned())
+    }
+}
+
+fn parse_u64(args: &ArgMatches, name: &str) -> Result<u64, Error> {
+    let value = args.value_of(name).unwrap();
+    value.parse::<u64>().map_err(|_| {
+        ErrorKind::GenericError(format!("Invalid value for {}: {}", name, value)).into()
+    })
+}
+
+fn parse_fee_base(args: &ArgMatches) -> Result<Option<u64>, Error> {
+    match args.value_of("fee_base") {
+        Some(_) => Ok(Some(parse_u64(args, "fee_base")?)),
+        None => Ok(None),
+    }
+}
+
+fn parse_amount(args: &ArgMatches) -> Result<u64, Error> {
+    let value = args.value_of("amount").unwrap();
+    amount_from_hr_string(value)
+        .map_err(|_| ErrorKind::GenericError(format!("Invalid amount: {}", value)).into())
+}
+
+fn print_info(info: &WalletInfo) {
+    let mut table = Table::new();
+    table.add_row(row![bFG->"Confirmed total", amount_to_hr_string(info.total, false)]);
+    table.add_row(row![
+        bFY->"Awaiting confirmation",
+        amount_to_hr_string(info.amount_awaiting_confirmation, false)
+    ]);
+    table.add_row(row![
+        bFY->"Awaiting finalization",
+        amount_to_hr_string(info.amount_awaiting_finalization, false)
+    ]);
+    table.add_row(row![
+        bFY->"Immature coinbase",
+        amount_to_hr_string(info.amount_immature, false)
+    ]);
+    table.add_row(row![
+        bFR->"Locked",
+        amount_to_hr_string(info.amount_locked, false)
+    ]);
+    table.add_row(row![
+        bFG->"Currently spendable",
+        amount_to_hr_string(info.amount_currently_spendable, false)
+    ]);
+    println!("Wallet summary at height {}:", info.last_confirmed_height);
+    table.printstd();
+}
+
+fn print_txs(txs: &[TxLogEntry]) {
+    let mut table = Table::new();
+    table.set_titles(row![
+        bMG->"Id",
+        bMG->"Type",
+        bMG->"Slate Id",
+        bMG->"Created",
+        bMG->"Confirmed",
+        bMG->"Received",
+        bMG->"Sent",
+    ]);
+    for tx in txs {
+        table.add_row(row![
+            tx.id,
+            tx.tx_type,
+            tx.tx_slate_id
+                .map(|id| id.to_string())
+                .unwrap_or("None".to_owned()),
+            tx.creation_ts.format("%Y-%m-%d %H:%M:%S"),
+            tx.confirmed,
+            amount_to_hr_string(tx.amount_credited, true),
+            amount_to_hr_string(tx.amount_debited, true),
+        ]);
+    }
+    table.printstd();
+}
@@ -0,0 +1,202 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// Generates JSON Schema and TypeScript definitions of the bridge responses:
+//
+//   cargo run --features schema --bin ironbelly-schema -- \
+//       schema/responses.json ../src/bridges/responses.ts
+use schemars::gen::SchemaGenerator;
+use schemars::schema::{InstanceType, RootSchema, Schema, SchemaObject, SingleOrVec};
+use schemars::JsonSchema;
+use std::collections::BTreeMap;
+use std::env;
+use std::fs;
+use std::path::Path;
+use std::process::exit;
+use wallet::responses::*;
+
+fn main() {
+    let args: Vec<String> = env::args().collect();
+    if args.len() != 3 {
+        eprintln!("Usage: {} <schema.json> <responses.ts>", args[0]);
+        exit(1);
+    }
+
+    let mut gen = SchemaGenerator::default();
+    let mut responses = vec![];
+    macro_rules! add {
+        ($($ty:ty),*) => {
+            $(
+                gen.subschema_for::<$ty>();
+                responses.push(<$ty>::schema_name());
+            )*
+        };
+    }
+    add!(
+        EmptyResponse,
+        OpenWalletResponse,
+        PhraseResponse,
+        MnemonicValidateResponse,
+        WalletsListResponse,
+        AccountsListResponse,
+        AccountResponse,
+        WalletScanOutputsResponse,
+        WalletPmmrRangeResponse,
+        TxGetResponse,
+        TxsGetResponse,
+        TxStrategiesResponse,
+        TxFeeEstimateResponse,
+        TxCreateResponse,
+        TxTransferResponse,
+        TxReceiveResponse,
+        TxFinalizeResponse,
+        TxIssueInvoiceResponse,
+        TxProcessInvoiceResponse,
+        TxSendAddressResponse,
+        PaymentProofExportResponse,
+        PaymentProofVerifyResponse,
+        SlatepackDecodeResponse,
+        GrinAddressResponse,
+        StartListenResponse
+    );
+    let definitions = gen.take_definitions();
+
+    let mut root = RootSchema::default();
+    root.schema.metadata().title = Some("Ironbelly bridge responses".to_owned());
+    root.schema.metadata().description = Some(format!("version {}", RESPONSE_VERSION));
+    root.schema.subschemas().one_of = Some(
+        responses
+            .iter()
+            .map(|name| Schema::new_ref(format!("#/definitions/{}", name)))
+            .collect(),
+    );
+    root.definitions = definitions.clone();
+    write(
+        &args[1],
+        serde_json::to_string_pretty(&root).expect("schema is serializable") + "\n",
+    );
+    write(&args[2], typescript(&definitions));
+}
+
+fn write(path: &str, content: String) {
+    if let Some(dir) = Path::new(path).parent() {
+        let _ = fs::create_dir_all(dir);
+    }
+    if let Err(e) = fs::write(path, content) {
+        eprintln!("Can not write {}: {}", path, e);
+        exit(1);
+    }
+}
+
+fn typescript(definitions: &BTreeMap<String, Schema>) -> String {
+    let mut out = String::new();
+    out.push_str("// Generated by `cargo run --features schema --bin ironbelly-schema`.\n");
+    out.push_str("// Do not edit, change rust/src/responses.rs instead.\n\n");
+    out.push_str(&format!(
+        "export const RESPONSE_VERSION = {}\n",
+        RESPONSE_VERSION
+    ));
+    for (name, schema) in definitions {
+        let object = match schema {
+            Schema::Object(object) => object,
+            Schema::Bool(_) => continue,
+        };
+        out.push('\n');
+        if let Some(description) = object
+            .metadata
+            .as_ref()
+            .and_then(|m| m.description.as_ref())
+        {
+            out.push_str(&format!("/** {} */\n", description));
+        }
+        match object.object.as_ref() {
+            Some(validation) if is_type(object, InstanceType::Object) => {
+                out.push_str(&format!("export interface {} {{\n", name));
+                for (field, schema) in &validation.properties {
+                    let optional = if validation.required.contains(field) {
+                        ""
+                    } else {
+                        "?"
+                    };
+                    out.push_str(&format!("  {}{}: {}\n", field, optional, ts_type(schema)));
+                }
+                out.push_str("}\n");
+            }
+            _ => out.push_str(&format!("export type {} = {}\n", name, ts_object(object))),
+        }
+    }
+    out
+}
+
+fn is_type(object: &SchemaObject, instance_type: InstanceType) -> bool {
+    match &object.instance_type {
+        Some(SingleOrVec::Single(t)) => **t == instance_type,
+        _ => false,
+    }
+}
+
+fn ts_type(schema: &Schema) -> String {
+    match schema {
+        Schema::Bool(true) => "unknown".to_owned(),
+        Schema::Bool(false) => "never".to_owned(),
+        Schema::Object(object) => ts_object(object),
+    }
+}
+
+fn ts_object(object: &SchemaObject) -> String {
+    if let Some(reference) = &object.reference {
+        return reference.rsplit('/').next().unwrap_or("unknown").to_owned();
+    }
+    if let Some(values) = &object.enum_values {
+        return values
+            .iter()
+            .map(|v| v.to_string())
+            .collect::<Vec<_>>()
+            .join(" | ");
+    }
+    if let Some(subschemas) = &object.subschemas {
+        let variants = subschemas
+            .any_of
+            .as_ref()
+            .or_else(|| subschemas.one_of.as_ref())
+            .or_else(|| subschemas.all_of.as_ref());
+        if let Some(variants) = variants {
+            return variants.iter().map(ts_type).collect::<Vec<_>>().join(" | ");
+        }
+    }
+    match &object.instance_type {
+        Some(SingleOrVec::Single(t)) => ts_instance_type(t, object),
+        Some(SingleOrVec::Vec(types)) => types
+            .iter()
+            .map(|t| ts_instance_type(t, object))
+            .collect::<Vec<_>>()
+            .join(" | "),
+        None => "unknown".to_owned(),
+    }
+}
+
+fn ts_instance_type(instance_type: &InstanceType, object: &SchemaObject) -> String {
+    match instance_type {
+        InstanceType::Null => "null".to_owned(),
+        InstanceType::Boolean => "boolean".to_owned(),
+        InstanceType::Integer | InstanceType::Number => "number".to_owned(),
+        InstanceType::String => "string".to_owned(),
+        InstanceType::Array => match object.array.as_ref().and_then(|a| a.items.as_ref()) {
+            Some(SingleOrVec::Single(items)) => format!("Array<{}>", ts_type(items)),
+            _ => "Array<unknown>".to_owned(),
+        },
+        InstanceType::Object => "{ [key: string]: unknown }".to_owned(),
+    }
+}
@@ -0,0 +1,116 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// Slatepack addresses of the other party of transactions. The tx log has no
+// place for them, so they are kept by slate id in a JSON file next to the
+// wallet database, which also puts them into backups.
+use crate::errors::{Error, ErrorKind};
+use crate::WalletInstance;
+use failure::ResultExt;
+use grin_wallet_config::GRIN_WALLET_DIR;
+use grin_wallet_libwallet::{NodeClient, SlatepackAddress, TxLogEntry};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use uuid::Uuid;
+
+const COUNTERPARTIES_FILE: &str = "counterparties.json";
+const COUNTERPARTIES_TMP_FILE: &str = "counterparties.json.tmp";
+
+/// Remember `address` as the other party of the transaction
+pub(crate) fn save_counterparty<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    slate_id: Uuid,
+    address: &SlatepackAddress,
+) -> Result<(), Error> {
+    // The wallet lock keeps concurrent calls from losing each other's writes
+    let mut w_lock = wallet.lock();
+    let path = file_path(&w_lock.lc_provider()?.get_top_level_directory()?);
+    let mut addresses = load(&path)?;
+    addresses.insert(slate_id.to_string(), address.to_string());
+    let json = serde_json::to_string_pretty(&addresses).context(ErrorKind::Format)?;
+    write(&path, &json)
+}
+
+/// Addresses of the other party of `txs` by slate id, transactions without
+/// a known address are left out
+pub(crate) fn counterparty_addresses<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    txs: &[TxLogEntry],
+) -> Result<BTreeMap<String, String>, Error> {
+    let mut w_lock = wallet.lock();
+    let path = file_path(&w_lock.lc_provider()?.get_top_level_directory()?);
+    let addresses = load(&path)?;
+    Ok(txs
+        .iter()
+        .filter_map(|tx| tx.tx_slate_id)
+        .filter_map(|id| {
+            let id = id.to_string();
+            addresses.get(&id).map(|address| (id, address.clone()))
+        })
+        .collect())
+}
+
+fn file_path(data_dir: &str) -> PathBuf {
+    Path::new(data_dir)
+        .join(GRIN_WALLET_DIR)
+        .join(COUNTERPARTIES_FILE)
+}
+
+/// Known addresses, none if the file is missing. A file which can't be parsed
+/// is treated as empty too, the addresses are only shown along with the
+/// transactions and must not make them fail.
+fn load(path: &Path) -> Result<BTreeMap<String, String>, Error> {
+    if !path.is_file() {
+        return Ok(BTreeMap::new());
+    }
+    let json = fs::read_to_string(path).context(ErrorKind::IO)?;
+    Ok(serde_json::from_str(&json).unwrap_or_else(|e| {
+        warn!("Ignoring unreadable {}: {}", path.display(), e);
+        BTreeMap::new()
+    }))
+}
+
+/// Replace the file at once, so an interrupted write doesn't leave a
+/// truncated one behind
+fn write(path: &Path, json: &str) -> Result<(), Error> {
+    let tmp_path = path.with_file_name(COUNTERPARTIES_TMP_FILE);
+    fs::write(&tmp_path, json).context(ErrorKind::IO)?;
+    fs::rename(&tmp_path, path).context(ErrorKind::IO)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn unreadable_file_is_treated_as_empty() {
+        let dir = Path::new("target/test_output/counterparty_unreadable_file");
+        let _ = fs::remove_dir_all(dir);
+        fs::create_dir_all(dir).unwrap();
+        let path = dir.join(COUNTERPARTIES_FILE);
+
+        let mut addresses = BTreeMap::new();
+        addresses.insert("slate".to_owned(), "grin1address".to_owned());
+        write(&path, &serde_json::to_string(&addresses).unwrap()).unwrap();
+        assert_eq!(load(&path).unwrap(), addresses);
+        assert!(!dir.join(COUNTERPARTIES_TMP_FILE).exists());
+
+        fs::write(&path, "{\"slate\": \"grin1").unwrap();
+        assert!(load(&path).unwrap().is_empty());
+        let _ = fs::remove_dir_all(dir);
+    }
+}
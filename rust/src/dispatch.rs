@@ -0,0 +1,662 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// JSON-RPC style entry point shared by the iOS and Android bindings.
+//
+// Every operation is registered once in `METHODS` and takes its arguments
+// as a JSON object, e.g. `call(handle, "tx_create", r#"{"amount": 1}"#)`.
+// Operations which don't need an opened wallet ignore `handle`. Results are
+// the versioned structs from `responses`.
+use super::*;
+use handles::{active_wallet, ActiveWallet, Handle, OpenedWallet, API_SERVERS, WALLETS};
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+
+type Method = fn(Handle, Value) -> Result<String, Error>;
+
+lazy_static! {
+    static ref METHODS: HashMap<&'static str, Method> = {
+        let mut m: HashMap<&'static str, Method> = HashMap::new();
+        m.insert("open_wallet", open_wallet_method);
+        m.insert("close_wallet", close_wallet_method);
+        m.insert("seed_new", seed_new_method);
+        m.insert("wallet_init", wallet_init_method);
+        m.insert("mnemonic_validate", mnemonic_validate_method);
+        m.insert("wallets_list", wallets_list_method);
+        m.insert("wallet_delete", wallet_delete_method);
+        m.insert("wallet_change_password", wallet_change_password_method);
+        m.insert("wallet_backup_export", wallet_backup_export_method);
+        m.insert("wallet_backup_import", wallet_backup_import_method);
+        m.insert("wallet_scan_outputs", wallet_scan_outputs_method);
+        m.insert("wallet_pmmr_range", wallet_pmmr_range_method);
+        m.insert("wallet_phrase", wallet_phrase_method);
+        m.insert("accounts_list", accounts_list_method);
+        m.insert("account_create", account_create_method);
+        m.insert("account_rename", account_rename_method);
+        m.insert(
+            "wallet_set_active_account",
+            wallet_set_active_account_method,
+        );
+        m.insert("tx_get", tx_get_method);
+        m.insert("txs_get", txs_get_method);
+        m.insert("tx_strategies", tx_strategies_method);
+        m.insert("tx_fee_estimate", tx_fee_estimate_method);
+        m.insert("tx_create", tx_create_method);
+        m.insert(
+            "tx_transfer_between_accounts",
+            tx_transfer_between_accounts_method,
+        );
+        m.insert("tx_cancel", tx_cancel_method);
+        m.insert("tx_receive", tx_receive_method);
+        m.insert("tx_finalize", tx_finalize_method);
+        m.insert("tx_issue_invoice", tx_issue_invoice_method);
+        m.insert("tx_process_invoice", tx_process_invoice_method);
+        m.insert("tx_send_address", tx_send_address_method);
+        m.insert("tx_post", tx_post_method);
+        m.insert("payment_proof_export", payment_proof_export_method);
+        m.insert("payment_proof_verify", payment_proof_verify_method);
+        m.insert("slatepack_decode", slatepack_decode_method);
+        m.insert(
+            "slatepack_decode_with_wallet",
+            slatepack_decode_with_wallet_method,
+        );
+        m.insert("get_grin_address", get_grin_address_method);
+        m.insert("start_listen_with_http", start_listen_with_http_method);
+        m.insert("stop_listen_with_http", stop_listen_with_http_method);
+        m.insert("create_tor_config", create_tor_config_method);
+        m
+    };
+}
+
+/// Run `method` with JSON encoded `params` and return JSON encoded result
+pub fn call(handle: Handle, method: &str, params: &str) -> Result<String, Error> {
+    let method_fn = METHODS
+        .get(method)
+        .ok_or_else(|| ErrorKind::UnknownMethod(method.to_owned()))?;
+    let params = if params.trim().is_empty() {
+        Value::Object(serde_json::Map::new())
+    } else {
+        serde_json::from_str(params)
+            .map_err(|e| ErrorKind::InvalidParams(method.to_owned(), e.to_string()))?
+    };
+    debug!("Calling {}", method);
+    method_fn(handle, params)
+}
+
+fn parse<T: DeserializeOwned>(method: &str, params: Value) -> Result<T, Error> {
+    serde_json::from_value(params)
+        .map_err(|e| Error::from(ErrorKind::InvalidParams(method.to_owned(), e.to_string())))
+}
+
+/// Get the wallet behind `handle` together with the keychain mask passed as
+/// `keychain_mask` in params, which a wallet opened with `use_mask` requires
+fn opened_wallet(
+    handle: Handle,
+    params: &Value,
+) -> Result<(ActiveWallet, Option<SecretKey>), Error> {
+    let opened = active_wallet(handle)?;
+    let mask = match params.get("keychain_mask") {
+        Some(Value::String(mask)) => Some(keychain_mask_from_hex(mask)?),
+        Some(Value::Null) | None => None,
+        Some(_) => return Err(ErrorKind::InvalidKeychainMask().into()),
+    };
+    opened.check_mask(mask.as_ref())?;
+    Ok((opened, mask))
+}
+
+#[derive(Deserialize)]
+struct OpenWalletParams {
+    config: String,
+    password: String,
+    /// Mask the keychain and return the mask, which then has to be passed
+    /// to every call on the wallet
+    #[serde(default)]
+    use_mask: bool,
+}
+
+fn open_wallet_method(_: Handle, params: Value) -> Result<String, Error> {
+    let p: OpenWalletParams = parse("open_wallet", params)?;
+    if p.use_mask {
+        let (opened, mask) = OpenedWallet::open_masked(&p.config, &p.password)?;
+        to_json(&OpenWalletResponse::new(
+            WALLETS.insert(opened),
+            Some(keychain_mask_to_hex(&mask)),
+        ))
+    } else {
+        let opened = OpenedWallet::open(&p.config, &p.password)?;
+        to_json(&OpenWalletResponse::new(WALLETS.insert(opened), None))
+    }
+}
+
+fn close_wallet_method(handle: Handle, _: Value) -> Result<String, Error> {
+    OpenedWallet::close(handle)?;
+    to_json(&EmptyResponse::new())
+}
+
+#[derive(Deserialize)]
+struct SeedNewParams {
+    seed_length: usize,
+}
+
+fn seed_new_method(_: Handle, params: Value) -> Result<String, Error> {
+    let p: SeedNewParams = parse("seed_new", params)?;
+    to_json(&seed_new(p.seed_length)?)
+}
+
+#[derive(Deserialize)]
+struct WalletInitParams {
+    config: String,
+    phrase: String,
+    password: String,
+}
+
+fn wallet_init_method(_: Handle, params: Value) -> Result<String, Error> {
+    let p: WalletInitParams = parse("wallet_init", params)?;
+    wallet_init(&p.config, &p.phrase, &p.password)?;
+    to_json(&EmptyResponse::new())
+}
+
+#[derive(Deserialize)]
+struct MnemonicValidateParams {
+    phrase: String,
+}
+
+fn mnemonic_validate_method(_: Handle, params: Value) -> Result<String, Error> {
+    let p: MnemonicValidateParams = parse("mnemonic_validate", params)?;
+    to_json(&mnemonic_validate(&p.phrase)?)
+}
+
+#[derive(Deserialize)]
+struct WalletsListParams {
+    wallet_dir: String,
+}
+
+fn wallets_list_method(_: Handle, params: Value) -> Result<String, Error> {
+    let p: WalletsListParams = parse("wallets_list", params)?;
+    to_json(&wallets_list(&p.wallet_dir)?)
+}
+
+#[derive(Deserialize)]
+struct WalletDeleteParams {
+    config: String,
+    password: String,
+}
+
+fn wallet_delete_method(_: Handle, params: Value) -> Result<String, Error> {
+    let p: WalletDeleteParams = parse("wallet_delete", params)?;
+    OpenedWallet::delete(&p.config, &p.password)?;
+    to_json(&EmptyResponse::new())
+}
+
+#[derive(Deserialize)]
+struct WalletChangePasswordParams {
+    config: String,
+    old_password: String,
+    new_password: String,
+}
+
+fn wallet_change_password_method(_: Handle, params: Value) -> Result<String, Error> {
+    let p: WalletChangePasswordParams = parse("wallet_change_password", params)?;
+    OpenedWallet::change_password(&p.config, &p.old_password, &p.new_password)?;
+    to_json(&EmptyResponse::new())
+}
+
+#[derive(Deserialize)]
+struct WalletBackupParams {
+    config: String,
+    password: String,
+    backup_file: String,
+}
+
+fn wallet_backup_export_method(_: Handle, params: Value) -> Result<String, Error> {
+    let p: WalletBackupParams = parse("wallet_backup_export", params)?;
+    handles::backup_export(&p.config, &p.password, &p.backup_file)?;
+    to_json(&EmptyResponse::new())
+}
+
+fn wallet_backup_import_method(_: Handle, params: Value) -> Result<String, Error> {
+    let p: WalletBackupParams = parse("wallet_backup_import", params)?;
+    wallet_backup_import(&p.config, &p.password, &p.backup_file)?;
+    to_json(&EmptyResponse::new())
+}
+
+#[derive(Deserialize)]
+struct WalletScanOutputsParams {
+    last_retrieved_index: u64,
+    highest_index: u64,
+}
+
+fn wallet_scan_outputs_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: WalletScanOutputsParams = parse("wallet_scan_outputs", params)?;
+    to_json(&wallet_scan_outputs(
+        &opened.wallet,
+        mask.as_ref(),
+        p.last_retrieved_index,
+        p.highest_index,
+    )?)
+}
+
+fn wallet_pmmr_range_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, _) = opened_wallet(handle, &params)?;
+    to_json(&wallet_pmmr_range(&opened.wallet)?)
+}
+
+#[derive(Deserialize)]
+struct WalletPhraseParams {
+    wallet_dir: String,
+    password: String,
+}
+
+fn wallet_phrase_method(_: Handle, params: Value) -> Result<String, Error> {
+    let p: WalletPhraseParams = parse("wallet_phrase", params)?;
+    to_json(&wallet_phrase(&p.wallet_dir, &p.password)?)
+}
+
+#[derive(Deserialize)]
+struct AccountsListParams {
+    minimum_confirmations: u64,
+}
+
+fn accounts_list_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: AccountsListParams = parse("accounts_list", params)?;
+    to_json(&accounts_list(
+        &opened.wallet,
+        mask.as_ref(),
+        p.minimum_confirmations,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct AccountCreateParams {
+    label: String,
+}
+
+fn account_create_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: AccountCreateParams = parse("account_create", params)?;
+    to_json(&account_create(&opened.wallet, mask.as_ref(), &p.label)?)
+}
+
+#[derive(Deserialize)]
+struct AccountRenameParams {
+    old_label: String,
+    new_label: String,
+}
+
+fn account_rename_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: AccountRenameParams = parse("account_rename", params)?;
+    to_json(&account_rename(
+        &opened.wallet,
+        mask.as_ref(),
+        &p.old_label,
+        &p.new_label,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct WalletSetActiveAccountParams {
+    account: String,
+}
+
+fn wallet_set_active_account_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, _) = opened_wallet(handle, &params)?;
+    let p: WalletSetActiveAccountParams = parse("wallet_set_active_account", params)?;
+    to_json(&wallet_set_active_account(&opened.wallet, &p.account)?)
+}
+
+#[derive(Deserialize)]
+struct TxGetParams {
+    refresh_from_node: bool,
+    tx_slate_id: String,
+}
+
+fn tx_get_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: TxGetParams = parse("tx_get", params)?;
+    to_json(&tx_get(
+        &opened.wallet,
+        mask.as_ref(),
+        p.refresh_from_node,
+        &p.tx_slate_id,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct TxsGetParams {
+    minimum_confirmations: u64,
+    refresh_from_node: bool,
+}
+
+fn txs_get_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: TxsGetParams = parse("txs_get", params)?;
+    to_json(&txs_get(
+        &opened.wallet,
+        mask.as_ref(),
+        p.minimum_confirmations,
+        p.refresh_from_node,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct TxStrategiesParams {
+    amount: u64,
+    minimum_confirmations: u64,
+}
+
+fn tx_strategies_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, _) = opened_wallet(handle, &params)?;
+    let p: TxStrategiesParams = parse("tx_strategies", params)?;
+    to_json(&tx_strategies(
+        &opened.wallet,
+        &opened.config,
+        p.amount,
+        p.minimum_confirmations,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct TxFeeEstimateParams {
+    amount: u64,
+    minimum_confirmations: u64,
+    selection_strategy_is_use_all: bool,
+    #[serde(default)]
+    fee_base: Option<u64>,
+    #[serde(default)]
+    num_change_outputs: Option<usize>,
+}
+
+fn tx_fee_estimate_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, _) = opened_wallet(handle, &params)?;
+    let p: TxFeeEstimateParams = parse("tx_fee_estimate", params)?;
+    to_json(&tx_fee_estimate(
+        &opened.wallet,
+        &opened.config,
+        p.amount,
+        p.minimum_confirmations,
+        p.selection_strategy_is_use_all,
+        p.fee_base,
+        p.num_change_outputs,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct TxCreateParams {
+    amount: u64,
+    minimum_confirmations: u64,
+    selection_strategy_is_use_all: bool,
+    #[serde(default)]
+    recipient_address: Option<String>,
+    #[serde(default)]
+    fee_base: Option<u64>,
+}
+
+fn tx_create_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: TxCreateParams = parse("tx_create", params)?;
+    to_json(&tx_create(
+        &opened.wallet,
+        mask.as_ref(),
+        &opened.config,
+        p.amount,
+        p.minimum_confirmations,
+        p.selection_strategy_is_use_all,
+        p.recipient_address.as_deref(),
+        p.fee_base,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct TxTransferBetweenAccountsParams {
+    from: String,
+    to: String,
+    amount: u64,
+    minimum_confirmations: u64,
+    selection_strategy_is_use_all: bool,
+}
+
+fn tx_transfer_between_accounts_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: TxTransferBetweenAccountsParams = parse("tx_transfer_between_accounts", params)?;
+    to_json(&tx_transfer_between_accounts(
+        &opened.wallet,
+        mask.as_ref(),
+        &opened.config,
+        &p.from,
+        &p.to,
+        p.amount,
+        p.minimum_confirmations,
+        p.selection_strategy_is_use_all,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct TxCancelParams {
+    id: u32,
+}
+
+fn tx_cancel_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: TxCancelParams = parse("tx_cancel", params)?;
+    tx_cancel(&opened.wallet, mask.as_ref(), p.id)?;
+    to_json(&EmptyResponse::new())
+}
+
+#[derive(Deserialize)]
+struct TxReceiveParams {
+    account: String,
+    slatepack: String,
+}
+
+fn tx_receive_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: TxReceiveParams = parse("tx_receive", params)?;
+    to_json(&tx_receive(
+        &opened.wallet,
+        mask.as_ref(),
+        &p.account,
+        &p.slatepack,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct TxFinalizeParams {
+    slatepack: String,
+}
+
+fn tx_finalize_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: TxFinalizeParams = parse("tx_finalize", params)?;
+    to_json(&tx_finalize(&opened.wallet, mask.as_ref(), &p.slatepack)?)
+}
+
+#[derive(Deserialize)]
+struct TxIssueInvoiceParams {
+    amount: u64,
+    account: String,
+}
+
+fn tx_issue_invoice_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: TxIssueInvoiceParams = parse("tx_issue_invoice", params)?;
+    to_json(&tx_issue_invoice(
+        &opened.wallet,
+        mask.as_ref(),
+        p.amount,
+        &p.account,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct TxProcessInvoiceParams {
+    slatepack: String,
+    minimum_confirmations: u64,
+    selection_strategy_is_use_all: bool,
+}
+
+fn tx_process_invoice_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: TxProcessInvoiceParams = parse("tx_process_invoice", params)?;
+    to_json(&tx_process_invoice(
+        &opened.wallet,
+        mask.as_ref(),
+        &opened.config,
+        &p.slatepack,
+        p.minimum_confirmations,
+        p.selection_strategy_is_use_all,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct TxSendAddressParams {
+    address: String,
+    amount: u64,
+    minimum_confirmations: u64,
+    selection_strategy_is_use_all: bool,
+    #[serde(default)]
+    fee_base: Option<u64>,
+}
+
+fn tx_send_address_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: TxSendAddressParams = parse("tx_send_address", params)?;
+    to_json(&tx_send_address(
+        &opened.wallet,
+        mask.as_ref(),
+        &opened.config,
+        &p.address,
+        p.amount,
+        p.minimum_confirmations,
+        p.selection_strategy_is_use_all,
+        p.fee_base,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct TxPostParams {
+    tx_slate_id: String,
+}
+
+fn tx_post_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: TxPostParams = parse("tx_post", params)?;
+    tx_post(&opened.wallet, mask.as_ref(), &p.tx_slate_id)?;
+    to_json(&EmptyResponse::new())
+}
+
+#[derive(Deserialize)]
+struct PaymentProofExportParams {
+    tx_slate_id: String,
+}
+
+fn payment_proof_export_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: PaymentProofExportParams = parse("payment_proof_export", params)?;
+    to_json(&payment_proof_export(
+        &opened.wallet,
+        mask.as_ref(),
+        &p.tx_slate_id,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct PaymentProofVerifyParams {
+    proof: String,
+}
+
+fn payment_proof_verify_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: PaymentProofVerifyParams = parse("payment_proof_verify", params)?;
+    to_json(&payment_proof_verify(
+        &opened.wallet,
+        mask.as_ref(),
+        &p.proof,
+    )?)
+}
+
+#[derive(Deserialize)]
+struct SlatepackDecodeParams {
+    slatepack: String,
+}
+
+fn slatepack_decode_method(_: Handle, params: Value) -> Result<String, Error> {
+    let p: SlatepackDecodeParams = parse("slatepack_decode", params)?;
+    to_json(&slatepack_decode(&p.slatepack)?)
+}
+
+fn slatepack_decode_with_wallet_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: SlatepackDecodeParams = parse("slatepack_decode_with_wallet", params)?;
+    to_json(&slatepack_decode_with_wallet(
+        &opened.wallet,
+        mask.as_ref(),
+        &p.slatepack,
+    )?)
+}
+
+fn get_grin_address_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    to_json(&get_grin_address(&opened.wallet, mask.as_ref())?)
+}
+
+#[derive(Deserialize)]
+struct StartListenWithHttpParams {
+    api_listen_addr: String,
+}
+
+fn start_listen_with_http_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    if mask.is_some() {
+        return Err(ErrorKind::WalletIsMasked().into());
+    }
+    let p: StartListenWithHttpParams = parse("start_listen_with_http", params)?;
+    let mut apis = ApiServer::new();
+    start_listen_with_http(&opened.wallet, &p.api_listen_addr, &mut apis)?;
+    to_json(&StartListenResponse::new(
+        API_SERVERS.insert(Mutex::new(apis)),
+    ))
+}
+
+#[derive(Deserialize)]
+struct StopListenWithHttpParams {
+    api_server: Handle,
+}
+
+fn stop_listen_with_http_method(_: Handle, params: Value) -> Result<String, Error> {
+    let p: StopListenWithHttpParams = parse("stop_listen_with_http", params)?;
+    let apis = API_SERVERS.remove(p.api_server)?;
+    apis.lock().stop();
+    to_json(&EmptyResponse::new())
+}
+
+#[derive(Deserialize)]
+struct CreateTorConfigParams {
+    listen_addr: String,
+}
+
+fn create_tor_config_method(handle: Handle, params: Value) -> Result<String, Error> {
+    let (opened, mask) = opened_wallet(handle, &params)?;
+    let p: CreateTorConfigParams = parse("create_tor_config", params)?;
+    create_tor_config(
+        &opened.wallet,
+        mask.as_ref(),
+        &opened.config,
+        &p.listen_addr,
+    )?;
+    to_json(&EmptyResponse::new())
+}
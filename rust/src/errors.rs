@@ -4,8 +4,11 @@ use grin_keychain;
 use grin_wallet_impls;
 use grin_wallet_libwallet;
 use grin_wallet_util::OnionV3AddressError;
+use serde::{Deserialize, Serialize};
+use std::any::Any;
 use std::env;
 use std::fmt::{self, Display};
+use std::panic::{self, AssertUnwindSafe};
 
 /// Error definition
 #[derive(Debug)]
@@ -39,12 +42,56 @@ pub enum ErrorKind {
     #[fail(display = "Wallet doesn't exist")]
     WalletDoesntExist(),
 
+    /// Seed can't be decrypted with the given password
+    #[fail(display = "Wrong password")]
+    WrongPassword(),
+
+    /// Account doesn't exist in the wallet
+    #[fail(display = "Account {} not found", _0)]
+    AccountNotFound(String),
+
+    /// Seed or wallet database can't be read
+    #[fail(display = "Wallet data is corrupted: {}", _0)]
+    WalletDataCorrupted(String),
+
     /// Wallet cannot be opened
     #[fail(display = "Wallet can not be opened: {}", _0)]
     WalletCannotBeOpened(String),
 
-    /// Config cannot be parsed
-    #[fail(display = "Wallet can not be opened: {}", _0)]
+    /// Recovery phrase has unknown words, a wrong length or checksum
+    #[fail(display = "Invalid recovery phrase: {}", _0)]
+    InvalidMnemonic(String),
+
+    /// Wallet name can't be used as a directory name
+    #[fail(display = "Invalid wallet name: {}", _0)]
+    InvalidWalletName(String),
+
+    /// Wallet exists already and would be overwritten
+    #[fail(display = "Wallet {} already exists", _0)]
+    WalletAlreadyExists(String),
+
+    /// Backup archive can't be read or is of an unknown version
+    #[fail(display = "Invalid backup: {}", _0)]
+    InvalidBackup(String),
+
+    /// Wallet is opened and can't be deleted
+    #[fail(display = "Wallet {} is open", _0)]
+    WalletIsOpen(String),
+
+    /// Wallet was locked after being idle and has to be opened again
+    #[fail(display = "Wallet is locked")]
+    WalletLocked(),
+
+    /// Operation isn't available on a wallet opened with a keychain mask
+    #[fail(display = "Not available on a wallet opened with use_mask")]
+    WalletIsMasked(),
+
+    /// Keychain mask is missing, malformed or doesn't belong to the wallet
+    #[fail(display = "Invalid keychain mask")]
+    InvalidKeychainMask(),
+
+    /// Config cannot be parsed or has invalid values
+    #[fail(display = "Invalid wallet config: {}", _0)]
     ConfigCannotBeParsed(String),
 
     /// Error when formatting json
@@ -55,11 +102,258 @@ pub enum ErrorKind {
     #[fail(display = "Serde JSON error")]
     Format,
 
+    /// Handle passed from the app is unknown, closed or reused
+    #[fail(display = "Invalid handle: {}", _0)]
+    InvalidHandle(u64),
+
+    /// Method is not registered in the dispatcher
+    #[fail(display = "Unknown method: {}", _0)]
+    UnknownMethod(String),
+
+    /// Params passed to the dispatcher can not be parsed
+    #[fail(display = "Invalid params for {}: {}", _0, _1)]
+    InvalidParams(String, String),
+
+    /// Panic caught before it crossed the FFI boundary
+    #[fail(display = "Panic in {}: {}", _0, _1)]
+    Panic(String, String),
+
     /// Other
     #[fail(display = "Generic error: {}", _0)]
     GenericError(String),
 }
 
+/// Broad class of a failure, so the app can branch on it without
+/// string-matching messages
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    NodeUnreachable,
+    WrongPassword,
+    InsufficientFunds,
+    SlateIncompatible,
+    InvalidSlate,
+    WalletNotFound,
+    WalletCorrupted,
+    WalletLocked,
+    Account,
+    Transaction,
+    PaymentProof,
+    InvalidInput,
+    InvalidHandle,
+    Io,
+    Internal,
+}
+
+/// Error as it is returned across the FFI boundary
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct ErrorResponse {
+    /// Stable machine-readable code, e.g. `not_enough_funds`
+    pub code: String,
+    pub category: ErrorCategory,
+    /// Human readable message
+    pub message: String,
+    pub details: Option<serde_json::Value>,
+}
+
+impl ErrorKind {
+    /// Stable error code
+    pub fn code(&self) -> &'static str {
+        match self {
+            ErrorKind::WalletError(e) => impls_error_code(e),
+            ErrorKind::LibTX(_) => "libtx",
+            ErrorKind::LibWallet(e, _) => libwallet_error_code(e),
+            ErrorKind::Keychain(_) => "keychain",
+            ErrorKind::OnionV3Address(_) => "invalid_address",
+            ErrorKind::WalletDoesntExist() => "wallet_doesnt_exist",
+            ErrorKind::WrongPassword() => "wrong_password",
+            ErrorKind::AccountNotFound(_) => "account_not_found",
+            ErrorKind::WalletDataCorrupted(_) => "wallet_data_corrupted",
+            ErrorKind::WalletCannotBeOpened(_) => "wallet_cannot_be_opened",
+            ErrorKind::InvalidMnemonic(_) => "invalid_mnemonic",
+            ErrorKind::InvalidWalletName(_) => "invalid_wallet_name",
+            ErrorKind::WalletAlreadyExists(_) => "wallet_already_exists",
+            ErrorKind::InvalidBackup(_) => "invalid_backup",
+            ErrorKind::WalletIsOpen(_) => "wallet_is_open",
+            ErrorKind::WalletLocked() => "wallet_locked",
+            ErrorKind::WalletIsMasked() => "wallet_is_masked",
+            ErrorKind::InvalidKeychainMask() => "invalid_keychain_mask",
+            ErrorKind::ConfigCannotBeParsed(_) => "config_cannot_be_parsed",
+            ErrorKind::IO => "io",
+            ErrorKind::Format => "format",
+            ErrorKind::InvalidHandle(_) => "invalid_handle",
+            ErrorKind::UnknownMethod(_) => "unknown_method",
+            ErrorKind::InvalidParams(_, _) => "invalid_params",
+            ErrorKind::Panic(_, _) => "panic",
+            ErrorKind::GenericError(_) => "generic",
+        }
+    }
+
+    /// Category the error belongs to
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            ErrorKind::WalletError(e) => impls_error_category(e),
+            ErrorKind::LibTX(_) => ErrorCategory::Transaction,
+            ErrorKind::LibWallet(e, _) => libwallet_error_category(e),
+            ErrorKind::Keychain(_) => ErrorCategory::Internal,
+            ErrorKind::OnionV3Address(_) => ErrorCategory::InvalidInput,
+            ErrorKind::WalletDoesntExist() => ErrorCategory::WalletNotFound,
+            ErrorKind::WrongPassword() => ErrorCategory::WrongPassword,
+            ErrorKind::AccountNotFound(_) => ErrorCategory::Account,
+            ErrorKind::WalletDataCorrupted(_) => ErrorCategory::WalletCorrupted,
+            ErrorKind::WalletCannotBeOpened(_) => ErrorCategory::WalletNotFound,
+            ErrorKind::InvalidMnemonic(_) => ErrorCategory::InvalidInput,
+            ErrorKind::InvalidWalletName(_) => ErrorCategory::InvalidInput,
+            ErrorKind::WalletAlreadyExists(_) => ErrorCategory::InvalidInput,
+            ErrorKind::InvalidBackup(_) => ErrorCategory::InvalidInput,
+            ErrorKind::WalletIsOpen(_) => ErrorCategory::InvalidInput,
+            ErrorKind::WalletLocked() => ErrorCategory::WalletLocked,
+            ErrorKind::WalletIsMasked() => ErrorCategory::InvalidInput,
+            ErrorKind::InvalidKeychainMask() => ErrorCategory::WrongPassword,
+            ErrorKind::ConfigCannotBeParsed(_) => ErrorCategory::InvalidInput,
+            ErrorKind::IO => ErrorCategory::Io,
+            ErrorKind::Format => ErrorCategory::Internal,
+            ErrorKind::InvalidHandle(_) => ErrorCategory::InvalidHandle,
+            ErrorKind::UnknownMethod(_) => ErrorCategory::InvalidInput,
+            ErrorKind::InvalidParams(_, _) => ErrorCategory::InvalidInput,
+            ErrorKind::Panic(_, _) => ErrorCategory::Internal,
+            ErrorKind::GenericError(_) => ErrorCategory::Internal,
+        }
+    }
+
+    /// Additional structured information, if there is any
+    pub fn details(&self) -> Option<serde_json::Value> {
+        match self {
+            ErrorKind::LibWallet(
+                grin_wallet_libwallet::Error::NotEnoughFunds {
+                    available, needed, ..
+                },
+                _,
+            ) => Some(serde_json::json!({
+                "available": available,
+                "needed": needed,
+            })),
+            ErrorKind::LibWallet(e, _) => Some(serde_json::json!({ "cause": format!("{:?}", e) })),
+            ErrorKind::WalletError(e) => Some(serde_json::json!({ "cause": format!("{:?}", e) })),
+            ErrorKind::LibTX(e) => Some(serde_json::json!({ "cause": format!("{:?}", e) })),
+            ErrorKind::Keychain(e) => Some(serde_json::json!({ "cause": format!("{:?}", e) })),
+            ErrorKind::OnionV3Address(e) => {
+                Some(serde_json::json!({ "cause": format!("{:?}", e) }))
+            }
+            _ => None,
+        }
+    }
+}
+
+fn libwallet_error_code(error: &grin_wallet_libwallet::Error) -> &'static str {
+    use grin_wallet_libwallet::Error as E;
+    match error {
+        E::NotEnoughFunds { .. } => "not_enough_funds",
+        E::Fee(_) => "fee",
+        E::ClientCallback(_) => "node_client",
+        E::Compatibility(_) => "slate_incompatible",
+        E::UnknownSlateVersion(_) => "unknown_slate_version",
+        E::SlateValidation => "slate_validation",
+        E::SlatepackDeser(_) => "slatepack_deserialization",
+        E::SlatepackDecryption(_) => "slatepack_decryption",
+        E::Lifecycle(m) if is_wrong_password_message(m) => "wrong_password",
+        E::Lifecycle(_) => "lifecycle",
+        E::InvalidKeychainMask => "invalid_keychain_mask",
+        E::UnknownAccountLabel(_) => "unknown_account",
+        E::AccountLabelAlreadyExists(_) => "account_already_exists",
+        E::TransactionDoesntExist(_) => "tx_doesnt_exist",
+        E::TransactionNotCancellable(_) => "tx_not_cancellable",
+        E::TransactionAlreadyConfirmed => "tx_already_confirmed",
+        E::TransactionExpired => "tx_expired",
+        E::TransactionAlreadyReceived(_) => "tx_already_received",
+        E::PaymentProof(_) => "payment_proof",
+        E::PaymentProofRetrieval(_) => "payment_proof_retrieval",
+        E::PaymentProofParsing(_) => "payment_proof_parsing",
+        E::ArgumentError(_) => "argument",
+        // e.g. the private context of a slate which has been finalized already
+        E::Backend(m) if m.contains("Not Found") => "not_found",
+        _ => "libwallet",
+    }
+}
+
+fn libwallet_error_category(error: &grin_wallet_libwallet::Error) -> ErrorCategory {
+    use grin_wallet_libwallet::Error as E;
+    match error {
+        E::NotEnoughFunds { .. } | E::Fee(_) => ErrorCategory::InsufficientFunds,
+        E::ClientCallback(_) => ErrorCategory::NodeUnreachable,
+        E::Compatibility(_) | E::UnknownSlateVersion(_) => ErrorCategory::SlateIncompatible,
+        E::SlateValidation | E::SlatepackDeser(_) | E::SlatepackDecryption(_) => {
+            ErrorCategory::InvalidSlate
+        }
+        E::Lifecycle(m) if is_wrong_password_message(m) => ErrorCategory::WrongPassword,
+        E::InvalidKeychainMask => ErrorCategory::WrongPassword,
+        E::UnknownAccountLabel(_) | E::AccountLabelAlreadyExists(_) => ErrorCategory::Account,
+        E::TransactionDoesntExist(_)
+        | E::TransactionNotCancellable(_)
+        | E::TransactionAlreadyConfirmed
+        | E::TransactionExpired
+        | E::TransactionAlreadyReceived(_) => ErrorCategory::Transaction,
+        E::PaymentProof(_) | E::PaymentProofRetrieval(_) | E::PaymentProofParsing(_) => {
+            ErrorCategory::PaymentProof
+        }
+        E::ArgumentError(_) => ErrorCategory::InvalidInput,
+        _ => ErrorCategory::Internal,
+    }
+}
+
+fn impls_error_code(error: &grin_wallet_impls::Error) -> &'static str {
+    use grin_wallet_impls::Error as E;
+    match error {
+        E::LibWallet(e) => libwallet_error_code(e),
+        E::Encryption => "wrong_password",
+        E::WalletSeedDoesntExist => "wallet_doesnt_exist",
+        E::IO(_) => "io",
+        _ => "wallet",
+    }
+}
+
+fn impls_error_category(error: &grin_wallet_impls::Error) -> ErrorCategory {
+    use grin_wallet_impls::Error as E;
+    match error {
+        E::LibWallet(e) => libwallet_error_category(e),
+        E::Encryption => ErrorCategory::WrongPassword,
+        E::WalletSeedDoesntExist => ErrorCategory::WalletNotFound,
+        E::IO(_) => ErrorCategory::Io,
+        _ => ErrorCategory::Internal,
+    }
+}
+
+/// The lifecycle provider reports a failed seed decryption only as a message
+fn is_wrong_password_message(message: &str) -> bool {
+    message.contains("is password correct")
+}
+
+/// Run `f` and turn a panic inside it into an `ErrorKind::Panic`,
+/// so it doesn't unwind into the caller of an exported function
+pub fn catch_panic<T, F>(context: &str, f: F) -> Result<T, Error>
+where
+    F: FnOnce() -> Result<T, Error>,
+{
+    match panic::catch_unwind(AssertUnwindSafe(f)) {
+        Ok(result) => result,
+        Err(payload) => {
+            let message = panic_message(&payload);
+            error!("Panic in {}: {}", context, message);
+            Err(ErrorKind::Panic(context.to_owned(), message).into())
+        }
+    }
+}
+
+fn panic_message(payload: &Box<dyn Any + Send>) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "Unknown panic".to_owned()
+    }
+}
+
 impl Fail for Error {
     fn cause(&self) -> Option<&dyn Fail> {
         self.inner.cause()
@@ -103,6 +397,21 @@ impl Error {
     pub fn backtrace(&self) -> Option<&Backtrace> {
         self.inner.backtrace()
     }
+    /// structured representation returned to the app
+    pub fn to_response(&self) -> ErrorResponse {
+        let kind = self.kind();
+        ErrorResponse {
+            code: kind.code().to_owned(),
+            category: kind.category(),
+            message: format!("{}", self.inner),
+            details: kind.details(),
+        }
+    }
+    /// structured representation serialized to JSON
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(&self.to_response())
+            .unwrap_or_else(|_| format!("{{\"code\":\"{}\"}}", self.kind().code()))
+    }
 }
 
 impl From<ErrorKind> for Error {
@@ -150,3 +459,88 @@ impl From<grin_wallet_impls::Error> for Error {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+    use std::net::SocketAddr;
+
+    fn assert_panic_error<T: fmt::Debug>(result: Result<T, Error>, context: &str) {
+        let error = result.expect_err("panic should be converted into an error");
+        match error.kind() {
+            ErrorKind::Panic(c, _) => assert_eq!(c, context),
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+        let response: serde_json::Value = serde_json::from_str(&error.to_json()).unwrap();
+        assert_eq!(response["code"], "panic");
+        assert_eq!(response["category"], "internal");
+    }
+
+    #[test]
+    fn catch_panic_passes_results_through() {
+        assert_eq!(catch_panic("ok", || Ok(42)).unwrap(), 42);
+        let error = catch_panic::<(), _>("err", || Err(ErrorKind::IO.into())).unwrap_err();
+        assert_eq!(error.kind(), ErrorKind::IO);
+    }
+
+    #[test]
+    fn catch_panic_on_cstring_with_nul() {
+        assert_panic_error(
+            catch_panic("cstring", || Ok(CString::new("a\0b").unwrap())),
+            "cstring",
+        );
+    }
+
+    #[test]
+    fn catch_panic_on_socket_address_expect() {
+        assert_panic_error(
+            catch_panic("socket", || {
+                Ok("not an address"
+                    .parse::<SocketAddr>()
+                    .expect("unable to parse socket address"))
+            }),
+            "socket",
+        );
+    }
+
+    #[test]
+    fn catch_panic_on_invalid_string_expect() {
+        assert_panic_error(
+            catch_panic("string", || {
+                let res: Result<String, String> = Err("not utf-8".to_owned());
+                Ok(res.expect("Invalid string"))
+            }),
+            "string",
+        );
+    }
+
+    #[test]
+    fn catch_panic_on_out_of_bounds_index() {
+        assert_panic_error(
+            catch_panic("index", || {
+                let txs: Vec<u32> = vec![];
+                Ok(txs[0])
+            }),
+            "index",
+        );
+    }
+
+    #[test]
+    fn catch_panic_on_formatted_panic() {
+        let error = catch_panic::<(), _>("format", || panic!("failed with {}", 7)).unwrap_err();
+        assert_eq!(
+            error.kind(),
+            ErrorKind::Panic("format".to_owned(), "failed with 7".to_owned())
+        );
+    }
+
+    #[test]
+    fn missing_store_records_have_their_own_code() {
+        let kind = ErrorKind::LibWallet(
+            grin_wallet_libwallet::Error::Backend("DB Not Found Error: Slate id: 01".to_owned()),
+            String::new(),
+        );
+        assert_eq!(kind.code(), "not_found");
+    }
+}
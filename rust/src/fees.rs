@@ -0,0 +1,164 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// Fees are the fee weight of a transaction times a fee base. libwallet reads
+// the fee base from the chain globals, so a different one is set for the
+// current thread while inputs are selected. Calls on an opened wallet run
+// with the fee base of its config, see `handles::ActiveWallet`.
+use crate::errors::Error;
+use crate::responses::TxFeeEstimateResponse;
+use crate::{Config, WalletInstance};
+use grin_core::global;
+use grin_wallet_libwallet::{selection, wallet_lock, NodeClient};
+
+/// Fee and shape of a transaction sending `amount` from the active account,
+/// as `tx_create` would build it with the same arguments. `fee_base` and
+/// `num_change_outputs` default to the values of the config.
+pub fn tx_fee_estimate<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    config: &Config,
+    amount: u64,
+    minimum_confirmations: u64,
+    selection_strategy_is_use_all: bool,
+    fee_base: Option<u64>,
+    num_change_outputs: Option<usize>,
+) -> Result<TxFeeEstimateResponse, Error> {
+    let fee_base = effective_fee_base(config, fee_base)?;
+    let num_change_outputs = num_change_outputs.unwrap_or(config.num_change_outputs);
+    if num_change_outputs == 0 {
+        return Err(argument_error("num_change_outputs must be greater than 0"));
+    }
+    wallet_lock!(wallet, w);
+    let parent_key_id = w.parent_key_id();
+    let height = w.w2n_client().get_chain_tip()?.0;
+    let (coins, total, _, fee) = with_fee_base(fee_base, || {
+        selection::select_coins_and_fee(
+            &mut **w,
+            amount,
+            false,
+            height,
+            minimum_confirmations,
+            config.max_outputs,
+            num_change_outputs,
+            selection_strategy_is_use_all,
+            &parent_key_id,
+        )
+    })?;
+    // No change is left over if the inputs match amount and fee exactly
+    let outputs = if total == amount + fee {
+        1
+    } else {
+        num_change_outputs + 1
+    };
+    Ok(TxFeeEstimateResponse::new(
+        fee,
+        fee_base,
+        fee / fee_base,
+        coins.len(),
+        outputs,
+        total,
+    ))
+}
+
+/// Fee base a transaction is built with, the config's `accept_fee_base` if
+/// none is given. Nodes don't relay transactions paying less than that.
+pub(crate) fn effective_fee_base(config: &Config, fee_base: Option<u64>) -> Result<u64, Error> {
+    match fee_base {
+        Some(fee_base) if fee_base < config.accept_fee_base => Err(argument_error(&format!(
+            "fee_base must be at least {}",
+            config.accept_fee_base
+        ))),
+        Some(fee_base) => Ok(fee_base),
+        None => Ok(config.accept_fee_base),
+    }
+}
+
+/// Run `f` with fees computed from `fee_base`
+pub(crate) fn with_fee_base<T>(fee_base: u64, f: impl FnOnce() -> T) -> T {
+    let _guard = FeeBaseGuard::set(fee_base);
+    f()
+}
+
+/// Restores the fee base of the thread when dropped, also when a panic
+/// unwinds to `catch_panic`, so FFI threads don't keep the override
+pub(crate) struct FeeBaseGuard(u64);
+
+impl FeeBaseGuard {
+    /// Use `fee_base` on the current thread until the guard is dropped
+    pub(crate) fn set(fee_base: u64) -> Self {
+        let guard = FeeBaseGuard(global::get_accept_fee_base());
+        global::set_local_accept_fee_base(fee_base);
+        guard
+    }
+}
+
+impl Drop for FeeBaseGuard {
+    fn drop(&mut self) {
+        global::set_local_accept_fee_base(self.0);
+    }
+}
+
+fn argument_error(message: &str) -> Error {
+    grin_wallet_libwallet::Error::ArgumentError(message.to_owned()).into()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::errors::catch_panic;
+    use std::sync::mpsc;
+    use std::thread;
+
+    #[test]
+    fn fee_base_is_restored() {
+        global::set_local_accept_fee_base(1000);
+        assert_eq!(with_fee_base(5000, global::get_accept_fee_base), 5000);
+        assert_eq!(global::get_accept_fee_base(), 1000);
+
+        let result = catch_panic::<(), _>("fees", || {
+            with_fee_base(5000, || panic!("selection failed"))
+        });
+        assert!(result.is_err());
+        assert_eq!(global::get_accept_fee_base(), 1000);
+    }
+
+    #[test]
+    fn fee_base_is_restored_after_a_panic_on_a_pool_thread() {
+        // The bindings run one call after another on the same threads
+        let (jobs, queue) = mpsc::channel::<Box<dyn FnOnce() -> u64 + Send>>();
+        let (results, received) = mpsc::channel();
+        let worker = thread::spawn(move || {
+            global::set_local_accept_fee_base(1000);
+            for job in queue {
+                results.send(job()).unwrap();
+            }
+        });
+
+        jobs.send(Box::new(|| {
+            let result = catch_panic::<(), _>("fees", || {
+                with_fee_base(5000, || panic!("selection failed"))
+            });
+            assert!(result.is_err());
+            global::get_accept_fee_base()
+        }))
+        .unwrap();
+        assert_eq!(received.recv().unwrap(), 1000);
+        jobs.send(Box::new(global::get_accept_fee_base)).unwrap();
+        assert_eq!(received.recv().unwrap(), 1000);
+
+        drop(jobs);
+        worker.join().unwrap();
+    }
+}
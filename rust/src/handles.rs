@@ -0,0 +1,365 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+use crate::errors::{Error, ErrorKind};
+use crate::fees::FeeBaseGuard;
+use grin_api::ApiServer;
+use grin_core::core::hash::{Hash, Hashed};
+use grin_util::secp::key::SecretKey;
+use grin_util::Mutex;
+use std::ops::Deref;
+use std::sync::{Arc, Once};
+use std::thread;
+use std::time::{Duration, Instant};
+
+/// Opaque value handed to the app instead of a raw pointer.
+/// Lower 32 bits are the slot index + 1, the 21 bits above are the slot
+/// generation, so `0` is never a valid handle and a reused slot gets a
+/// different handle. Handles fit into 53 bits, which JavaScript numbers hold
+/// exactly.
+pub type Handle = u64;
+
+const MAX_GENERATION: u32 = (1 << 21) - 1;
+/// How often opened wallets are checked for having been idle too long
+const AUTO_LOCK_CHECK_INTERVAL: Duration = Duration::from_secs(5);
+
+struct Slot<T> {
+    generation: u32,
+    value: Option<Arc<T>>,
+}
+
+/// Table of live objects addressed by generation-checked handles
+pub struct HandleRegistry<T> {
+    slots: Mutex<Vec<Slot<T>>>,
+}
+
+impl<T> HandleRegistry<T> {
+    pub fn new() -> Self {
+        HandleRegistry {
+            slots: Mutex::new(vec![]),
+        }
+    }
+
+    /// Store value and return a new handle for it
+    pub fn insert(&self, value: T) -> Handle {
+        let mut slots = self.slots.lock();
+        let value = Some(Arc::new(value));
+        let index = match slots.iter().position(|slot| slot.value.is_none()) {
+            Some(index) => {
+                let slot = &mut slots[index];
+                slot.generation = if slot.generation >= MAX_GENERATION {
+                    1
+                } else {
+                    slot.generation + 1
+                };
+                slot.value = value;
+                index
+            }
+            None => {
+                slots.push(Slot {
+                    generation: 1,
+                    value,
+                });
+                slots.len() - 1
+            }
+        };
+        to_handle(index, slots[index].generation)
+    }
+
+    /// Get the value behind the handle. The returned `Arc` stays valid even if
+    /// the handle is removed concurrently.
+    pub fn get(&self, handle: Handle) -> Result<Arc<T>, Error> {
+        let slots = self.slots.lock();
+        let (index, generation) = from_handle(handle)?;
+        match slots.get(index) {
+            Some(Slot {
+                generation: g,
+                value: Some(value),
+            }) if *g == generation => Ok(value.clone()),
+            _ => Err(ErrorKind::InvalidHandle(handle).into()),
+        }
+    }
+
+    /// Whether any live value matches `predicate`
+    pub fn any<F: Fn(&T) -> bool>(&self, predicate: F) -> bool {
+        let slots = self.slots.lock();
+        slots
+            .iter()
+            .filter_map(|slot| slot.value.as_ref())
+            .any(|value| predicate(value))
+    }
+
+    /// All live values
+    pub fn values(&self) -> Vec<Arc<T>> {
+        let slots = self.slots.lock();
+        slots.iter().filter_map(|slot| slot.value.clone()).collect()
+    }
+
+    /// Invalidate the handle and return the value it pointed to
+    pub fn remove(&self, handle: Handle) -> Result<Arc<T>, Error> {
+        let mut slots = self.slots.lock();
+        let (index, generation) = from_handle(handle)?;
+        match slots.get_mut(index) {
+            Some(slot) if slot.generation == generation && slot.value.is_some() => {
+                Ok(slot.value.take().unwrap())
+            }
+            _ => Err(ErrorKind::InvalidHandle(handle).into()),
+        }
+    }
+}
+
+fn to_handle(index: usize, generation: u32) -> Handle {
+    ((generation as u64) << 32) | (index as u64 + 1)
+}
+
+fn from_handle(handle: Handle) -> Result<(usize, u32), Error> {
+    let index = handle & 0xffff_ffff;
+    if index == 0 {
+        return Err(ErrorKind::InvalidHandle(handle).into());
+    }
+    Ok(((index - 1) as usize, (handle >> 32) as u32))
+}
+
+/// Wallet opened by the app together with the config it was opened with
+pub struct OpenedWallet {
+    pub wallet: crate::Wallet,
+    pub config: crate::Config,
+    /// Hash of the keychain mask if the wallet was opened masked. The mask
+    /// itself is only held by the app.
+    mask_hash: Option<Hash>,
+    activity: Mutex<Activity>,
+}
+
+struct Activity {
+    last_call: Instant,
+    /// Number of calls running on the wallet right now
+    calls: usize,
+    locked: bool,
+}
+
+impl OpenedWallet {
+    fn new(wallet: crate::Wallet, config: crate::Config, mask_hash: Option<Hash>) -> Self {
+        if config.auto_lock_timeout.is_some() {
+            start_auto_lock();
+        }
+        OpenedWallet {
+            wallet,
+            config,
+            mask_hash,
+            activity: Mutex::new(Activity {
+                last_call: Instant::now(),
+                calls: 0,
+                locked: false,
+            }),
+        }
+    }
+
+    pub fn open(config_json: &str, password: &str) -> Result<Self, Error> {
+        let config = crate::Config::from_str(config_json)?;
+        let wallet = crate::open_wallet(config_json, password)?;
+        Ok(OpenedWallet::new(wallet, config, None))
+    }
+
+    /// Open the wallet masked, returns the mask the app has to pass back
+    pub fn open_masked(config_json: &str, password: &str) -> Result<(Self, SecretKey), Error> {
+        let config = crate::Config::from_str(config_json)?;
+        let (wallet, mask) = crate::open_wallet_masked(config_json, password)?;
+        let opened = OpenedWallet::new(wallet, config, Some(mask_hash(&mask)));
+        Ok((opened, mask))
+    }
+
+    /// Close the wallet behind `handle` and invalidate the handle. Works on
+    /// a locked wallet too, which is how the app gets rid of its handle.
+    pub fn close(handle: Handle) -> Result<(), Error> {
+        let opened = WALLETS.get(handle)?;
+        opened.lock(false)?;
+        WALLETS.remove(handle)?;
+        Ok(())
+    }
+
+    /// The backend applies whatever mask it gets and would derive wrong keys
+    /// from a wrong one, so refuse a mask which isn't the one of this wallet
+    pub fn check_mask(&self, mask: Option<&SecretKey>) -> Result<(), Error> {
+        let matches = match (&self.mask_hash, mask) {
+            (Some(hash), Some(mask)) => *hash == mask_hash(mask),
+            (None, None) => true,
+            _ => false,
+        };
+        if !matches {
+            return Err(ErrorKind::InvalidKeychainMask().into());
+        }
+        Ok(())
+    }
+
+    /// Close the lifecycle provider, dropping the keychain, and refuse any
+    /// further calls. With `only_idle` only a wallet which is not in use and
+    /// had no calls for `auto_lock_timeout` is locked. A running listener
+    /// doesn't count as use, it can't receive once the wallet is locked.
+    fn lock(&self, only_idle: bool) -> Result<(), Error> {
+        {
+            let mut activity = self.activity.lock();
+            let idle = match self.config.auto_lock_timeout {
+                Some(timeout) => {
+                    activity.calls == 0
+                        && activity.last_call.elapsed() >= Duration::from_secs(timeout)
+                }
+                None => false,
+            };
+            if activity.locked || (only_idle && !idle) {
+                return Ok(());
+            }
+            activity.locked = true;
+        }
+        crate::close_wallet(&self.wallet)
+    }
+
+    /// Delete a wallet unless it is currently opened
+    pub fn delete(config_json: &str, password: &str) -> Result<(), Error> {
+        ensure_not_open(config_json)?;
+        crate::wallet_delete(config_json, password)
+    }
+
+    /// Change the password of a wallet unless it is currently opened, an
+    /// opened wallet would keep using the keychain of the old seed file
+    pub fn change_password(
+        config_json: &str,
+        old_password: &str,
+        new_password: &str,
+    ) -> Result<(), Error> {
+        ensure_not_open(config_json)?;
+        crate::wallet_change_password(config_json, old_password, new_password)
+    }
+}
+
+fn ensure_not_open(config_json: &str) -> Result<(), Error> {
+    let config = crate::Config::from_str(config_json)?;
+    let data_dir = config.data_dir();
+    if WALLETS.any(|opened| opened.config.data_dir() == data_dir) {
+        return Err(ErrorKind::WalletIsOpen(config.wallet_name().to_owned()).into());
+    }
+    Ok(())
+}
+
+/// Export a backup of a wallet. If the wallet is opened it is kept locked
+/// meanwhile, so its database isn't written while being copied.
+pub fn backup_export(config_json: &str, password: &str, backup_file: &str) -> Result<(), Error> {
+    let data_dir = crate::Config::from_str(config_json)?.data_dir();
+    let opened = WALLETS
+        .values()
+        .into_iter()
+        .find(|opened| opened.config.data_dir() == data_dir);
+    let _lock = opened.as_ref().map(|opened| opened.wallet.lock());
+    crate::wallet_backup_export(config_json, password, backup_file)
+}
+
+/// Opened wallet used by a call. It counts as in use, and so isn't locked,
+/// until this is dropped. Meanwhile fees on the calling thread are computed
+/// with the `accept_fee_base` of the wallet's config, the bindings may call
+/// from any thread, not only the one which opened the wallet.
+pub struct ActiveWallet(Arc<OpenedWallet>, FeeBaseGuard);
+
+impl Deref for ActiveWallet {
+    type Target = OpenedWallet;
+
+    fn deref(&self) -> &OpenedWallet {
+        &self.0
+    }
+}
+
+impl Drop for ActiveWallet {
+    fn drop(&mut self) {
+        let mut activity = self.0.activity.lock();
+        activity.calls -= 1;
+        activity.last_call = Instant::now();
+    }
+}
+
+/// Get the wallet behind `handle` for a call, fails with `WalletLocked` if
+/// it has been idle for too long
+pub fn active_wallet(handle: Handle) -> Result<ActiveWallet, Error> {
+    let opened = WALLETS.get(handle)?;
+    // The check for idle wallets may not have run yet
+    opened.lock(true)?;
+    {
+        let mut activity = opened.activity.lock();
+        if activity.locked {
+            return Err(ErrorKind::WalletLocked().into());
+        }
+        activity.calls += 1;
+    }
+    let fee_base = FeeBaseGuard::set(opened.config.accept_fee_base);
+    Ok(ActiveWallet(opened, fee_base))
+}
+
+/// Get a wallet for the bindings which don't take a keychain mask, these
+/// can't be used with a masked wallet
+pub fn unmasked_wallet(handle: Handle) -> Result<ActiveWallet, Error> {
+    let opened = active_wallet(handle)?;
+    opened.check_mask(None)?;
+    Ok(opened)
+}
+
+/// Lock wallets in the background once they have been idle for too long
+fn start_auto_lock() {
+    static START: Once = Once::new();
+    START.call_once(|| {
+        thread::spawn(|| loop {
+            thread::sleep(AUTO_LOCK_CHECK_INTERVAL);
+            for opened in WALLETS.values() {
+                if let Err(e) = opened.lock(true) {
+                    warn!("Failed to lock idle wallet: {}", e);
+                }
+            }
+        });
+    });
+}
+
+fn mask_hash(mask: &SecretKey) -> Hash {
+    mask.0.to_vec().hash()
+}
+
+lazy_static! {
+    /// Opened wallets
+    pub static ref WALLETS: HandleRegistry<OpenedWallet> = HandleRegistry::new();
+    /// Running HTTP listeners
+    pub static ref API_SERVERS: HandleRegistry<Mutex<ApiServer>> = HandleRegistry::new();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Largest integer a JavaScript number holds exactly
+    const MAX_SAFE_INTEGER: u64 = (1 << 53) - 1;
+
+    #[test]
+    fn handles_fit_into_javascript_numbers() {
+        assert!(to_handle(u32::MAX as usize - 1, MAX_GENERATION) <= MAX_SAFE_INTEGER);
+
+        let registry = HandleRegistry::new();
+        let handle = registry.insert(());
+        registry.remove(handle).unwrap();
+        registry.slots.lock()[0].generation = MAX_GENERATION - 1;
+        let last = registry.insert(());
+        assert!(last <= MAX_SAFE_INTEGER);
+        assert_eq!(from_handle(last).unwrap(), (0, MAX_GENERATION));
+
+        // The generation starts over, the last handle of the slot is invalid
+        registry.remove(last).unwrap();
+        let wrapped = registry.insert(());
+        assert_eq!(from_handle(wrapped).unwrap(), (0, 1));
+        assert!(registry.get(last).is_err());
+    }
+}
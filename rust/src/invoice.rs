@@ -0,0 +1,90 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// Invoice flow, the reverse of a send: the recipient issues an I1 slatepack
+// asking for an amount, the payer adds inputs and returns an I2 slatepack,
+// and the recipient finalizes it with `tx_finalize` and posts it.
+use crate::counterparty::save_counterparty;
+use crate::errors::Error;
+use crate::responses::{TxIssueInvoiceResponse, TxProcessInvoiceResponse};
+use crate::{
+    account_slatepack_address, decode_slatepack, encode_slatepack, slate_txs, Config, SecretKey,
+    WalletInstance,
+};
+use grin_wallet_api::Owner;
+use grin_wallet_libwallet::{InitTxArgs, IssueInvoiceTxArgs, NodeClient, SlateState};
+
+/// Request a payment into the given account, returns the tx log entry and
+/// the armored slatepack to hand to the payer
+pub fn tx_issue_invoice<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    amount: u64,
+    account: &str,
+) -> Result<TxIssueInvoiceResponse, Error> {
+    let api = Owner::new(wallet.clone(), None);
+    let args = IssueInvoiceTxArgs {
+        dest_acct_name: Some(account.to_owned()),
+        amount,
+        ..Default::default()
+    };
+    let slate = api.issue_invoice_tx(keychain_mask, args)?;
+    // The invoice is in the tx log of `account`, which may not be the active one
+    let txs = slate_txs(wallet, slate.id)?;
+    let own_address = account_slatepack_address(wallet, keychain_mask, Some(account))?;
+    Ok(TxIssueInvoiceResponse::new(
+        txs,
+        encode_slatepack(&slate, Some(own_address), None)?,
+    ))
+}
+
+/// Pay an invoice from the active account, returns the tx log entry and the
+/// armored slatepack to return to the issuer
+pub fn tx_process_invoice<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    config: &Config,
+    slate_armored: &str,
+    minimum_confirmations: u64,
+    selection_strategy_is_use_all: bool,
+) -> Result<TxProcessInvoiceResponse, Error> {
+    let api = Owner::new(wallet.clone(), None);
+    let (slate, issuer) = decode_slatepack(wallet, keychain_mask, slate_armored, None)?;
+    if slate.state != SlateState::Invoice1 {
+        return Err(grin_wallet_libwallet::Error::ArgumentError(
+            "not an invoice slatepack".to_owned(),
+        )
+        .into());
+    }
+    let args = InitTxArgs {
+        minimum_confirmations,
+        max_outputs: config.max_outputs as u32,
+        num_change_outputs: config.num_change_outputs as u32,
+        selection_strategy_is_use_all,
+        ..Default::default()
+    };
+    let slate = api.process_invoice_tx(keychain_mask, &slate, args)?;
+    api.tx_lock_outputs(keychain_mask, &slate)?;
+    if let Some(issuer) = &issuer {
+        save_counterparty(wallet, slate.id, issuer)?;
+    }
+    let txs = api.retrieve_txs(keychain_mask, false, None, Some(slate.id), None)?;
+    let own_address = account_slatepack_address(wallet, keychain_mask, None)?;
+    // Only the issuer can read the reply if it told its address
+    Ok(TxProcessInvoiceResponse::new(
+        txs.1,
+        encode_slatepack(&slate, Some(own_address), issuer)?,
+    ))
+}
@@ -14,56 +14,64 @@
  * limitations under the License.
  */
 use super::*;
+use errors::catch_panic;
+use handles::{unmasked_wallet, Handle, OpenedWallet, API_SERVERS, WALLETS};
 use simplelog::{Config, LevelFilter, SimpleLogger};
 use std::ffi::{CStr, CString};
 use std::os::raw::c_char;
 
 macro_rules! unwrap_string_to_c (
     ($func:expr, $error:expr) => (
-        match $func {
+        match catch_panic(concat!(file!(), ":", line!()), || $func).and_then(string_to_c) {
             Ok(res) => {
                 *$error = 0;
-                CString::new(res.to_owned()).unwrap().into_raw()
+                res.into_raw()
             }
             Err(e) => {
                 *$error = 1;
-                CString::new(
-                    serde_json::to_string(&format!("{}",e)).unwrap()).unwrap().into_raw()
+                error_to_c(&e)
             }
         }
         ));
 
 macro_rules! unwrap_to_c (
     ($func:expr, $error:expr) => (
-        match $func {
+        match catch_panic(concat!(file!(), ":", line!()), || $func) {
             Ok(res) => {
                 *$error = 0;
-                Box::into_raw(Box::new(res)) as usize
+                res as usize
             }
             Err(e) => {
                 *$error = 1;
-                CString::new(
-                    serde_json::to_string(&format!("{}",e)).unwrap()).unwrap().into_raw() as usize
+                error_to_c(&e) as usize
             }
         }
         ));
 
 macro_rules! ensure_wallet (
-    ($wallet_ptr:expr, $wallet:ident, $error:expr) => (
-        if ($wallet_ptr as *mut Wallet).as_mut().is_none() {
-            *$error = 1;
-            return CString::new("Wallet is NULL".to_owned())
-                .unwrap()
-                .into_raw();
-        }
-        let $wallet = ($wallet_ptr as *mut Wallet).as_mut().unwrap();
+    ($wallet_handle:expr, $wallet:ident, $config:ident) => (
+        // Kept until the end of the call, so the wallet isn't locked meanwhile
+        let opened = unmasked_wallet($wallet_handle as Handle)?;
+        let ($wallet, $config) = (opened.wallet.clone(), opened.config.clone());
+    );
+    ($wallet_handle:expr, $wallet:ident) => (
+        ensure_wallet!($wallet_handle, $wallet, _config);
     )
 );
 
 fn cstr_to_rust(s: *const c_char) -> String {
+    assert!(!s.is_null(), "NULL string passed");
     unsafe { CStr::from_ptr(s).to_string_lossy().into_owned() }
 }
 
+fn string_to_c(s: String) -> Result<CString, Error> {
+    CString::new(s).map_err(|e| Error::from(ErrorKind::GenericError(e.to_string())))
+}
+
+fn error_to_c(e: &Error) -> *mut c_char {
+    CString::new(e.to_json()).unwrap_or_default().into_raw()
+}
+
 #[no_mangle]
 pub unsafe extern "C" fn cstr_free(s: *mut c_char) {
     if s.is_null() {
@@ -74,14 +82,34 @@ pub unsafe extern "C" fn cstr_free(s: *mut c_char) {
 
 #[no_mangle]
 pub unsafe extern "C" fn c_set_logger() {
-    SimpleLogger::init(
-        if cfg!(debug_assertions) {
-            LevelFilter::Debug
-        } else {
-            LevelFilter::Info
-        },
-        Config::default(),
-    );
+    let _ = catch_panic("c_set_logger", || {
+        let _ = SimpleLogger::init(
+            if cfg!(debug_assertions) {
+                LevelFilter::Debug
+            } else {
+                LevelFilter::Info
+            },
+            Config::default(),
+        );
+        Ok(())
+    });
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn c_call(
+    handle: usize,
+    method: *const c_char,
+    params: *const c_char,
+    error: *mut u8,
+) -> *const c_char {
+    unwrap_string_to_c!(
+        dispatch::call(
+            handle as Handle,
+            &cstr_to_rust(method),
+            &cstr_to_rust(params)
+        ),
+        error
+    )
 }
 
 #[no_mangle]
@@ -91,24 +119,26 @@ pub unsafe extern "C" fn c_open_wallet(
     error: *mut u8,
 ) -> usize {
     unwrap_to_c!(
-        open_wallet(&cstr_to_rust(config_str), &cstr_to_rust(password)),
+        OpenedWallet::open(&cstr_to_rust(config_str), &cstr_to_rust(password))
+            .map(|opened| WALLETS.insert(opened)),
         error
     )
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn c_close_wallet(wallet_ptr: usize, error: *mut u8) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
-    let result = close_wallet(&wallet);
-    if result.is_ok() {
-        Box::from_raw(wallet);
-    };
-    unwrap_string_to_c!(result, error)
+pub unsafe extern "C" fn c_close_wallet(wallet_handle: usize, error: *mut u8) -> *const c_char {
+    unwrap_string_to_c!(
+        OpenedWallet::close(wallet_handle as Handle).and_then(|_| to_json(&EmptyResponse::new())),
+        error
+    )
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn c_seed_new(seed_length: u8, error: *mut u8) -> *const c_char {
-    unwrap_string_to_c!(seed_new(seed_length as usize), error)
+    unwrap_string_to_c!(
+        seed_new(seed_length as usize).and_then(|res| to_json(&res)),
+        error
+    )
 }
 
 #[no_mangle]
@@ -123,29 +153,59 @@ pub unsafe extern "C" fn c_wallet_init(
             &cstr_to_rust(config_str),
             &cstr_to_rust(phrase),
             &cstr_to_rust(password),
-        ),
+        )
+        .and_then(|_| to_json(&EmptyResponse::new())),
+        error
+    )
+}
+
+#[no_mangle]
+pub unsafe extern "C" fn c_wallet_change_password(
+    config_str: *const c_char,
+    old_password: *const c_char,
+    new_password: *const c_char,
+    error: *mut u8,
+) -> *const c_char {
+    unwrap_string_to_c!(
+        OpenedWallet::change_password(
+            &cstr_to_rust(config_str),
+            &cstr_to_rust(old_password),
+            &cstr_to_rust(new_password),
+        )
+        .and_then(|_| to_json(&EmptyResponse::new())),
         error
     )
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn c_wallet_scan_outputs(
-    wallet_ptr: usize,
+    wallet_handle: usize,
     last_retrieved_index: u64,
     highest_index: u64,
     error: *mut u8,
 ) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
     unwrap_string_to_c!(
-        wallet_scan_outputs(&wallet, last_retrieved_index, highest_index),
+        {
+            ensure_wallet!(wallet_handle, wallet);
+            wallet_scan_outputs(&wallet, None, last_retrieved_index, highest_index)
+                .and_then(|res| to_json(&res))
+        },
         error
     )
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn c_wallet_pmmr_range(wallet_ptr: usize, error: *mut u8) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
-    unwrap_string_to_c!(wallet_pmmr_range(&wallet), error)
+pub unsafe extern "C" fn c_wallet_pmmr_range(
+    wallet_handle: usize,
+    error: *mut u8,
+) -> *const c_char {
+    unwrap_string_to_c!(
+        {
+            ensure_wallet!(wallet_handle, wallet);
+            wallet_pmmr_range(&wallet).and_then(|res| to_json(&res))
+        },
+        error
+    )
 }
 
 #[no_mangle]
@@ -155,134 +215,184 @@ pub unsafe extern "C" fn c_wallet_phrase(
     error: *mut u8,
 ) -> *const c_char {
     unwrap_string_to_c!(
-        wallet_phrase(&cstr_to_rust(wallet_dir), &cstr_to_rust(password)),
+        wallet_phrase(&cstr_to_rust(wallet_dir), &cstr_to_rust(password))
+            .and_then(|res| to_json(&res)),
         error
     )
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn c_tx_get(
-    wallet_ptr: usize,
+    wallet_handle: usize,
     refresh_from_node: bool,
     tx_slate_id: *const c_char,
     error: *mut u8,
 ) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
     unwrap_string_to_c!(
-        tx_get(&wallet, refresh_from_node, &cstr_to_rust(tx_slate_id)),
+        {
+            ensure_wallet!(wallet_handle, wallet);
+            tx_get(&wallet, None, refresh_from_node, &cstr_to_rust(tx_slate_id))
+                .and_then(|res| to_json(&res))
+        },
         error
     )
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn c_txs_get(
-    wallet_ptr: usize,
+    wallet_handle: usize,
     minimum_confirmations: u64,
     refresh_from_node: bool,
     error: *mut u8,
 ) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
     unwrap_string_to_c!(
-        txs_get(&wallet, minimum_confirmations, refresh_from_node),
+        {
+            ensure_wallet!(wallet_handle, wallet);
+            txs_get(&wallet, None, minimum_confirmations, refresh_from_node)
+                .and_then(|res| to_json(&res))
+        },
         error
     )
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn c_tx_strategies(
-    wallet_ptr: usize,
+    wallet_handle: usize,
     amount: u64,
     minimum_confirmations: u64,
     error: *mut u8,
 ) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
-    unwrap_string_to_c!(tx_strategies(&wallet, amount, minimum_confirmations), error)
+    unwrap_string_to_c!(
+        {
+            ensure_wallet!(wallet_handle, wallet, config);
+            tx_strategies(&wallet, &config, amount, minimum_confirmations)
+                .and_then(|res| to_json(&res))
+        },
+        error
+    )
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn c_tx_create(
-    wallet_ptr: usize,
+    wallet_handle: usize,
     amount: u64,
     minimum_confirmations: u64,
     selection_strategy_is_use_all: bool,
     error: *mut u8,
 ) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
     unwrap_string_to_c!(
-        tx_create(
-            &wallet,
-            amount,
-            minimum_confirmations,
-            selection_strategy_is_use_all
-        ),
+        {
+            ensure_wallet!(wallet_handle, wallet, config);
+            tx_create(
+                &wallet,
+                None,
+                &config,
+                amount,
+                minimum_confirmations,
+                selection_strategy_is_use_all,
+                None,
+                None,
+            )
+            .and_then(|res| to_json(&res))
+        },
         error
     )
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn c_tx_cancel(wallet_ptr: usize, id: u32, error: *mut u8) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
-    unwrap_string_to_c!(tx_cancel(&wallet, id), error)
+pub unsafe extern "C" fn c_tx_cancel(
+    wallet_handle: usize,
+    id: u32,
+    error: *mut u8,
+) -> *const c_char {
+    unwrap_string_to_c!(
+        {
+            ensure_wallet!(wallet_handle, wallet);
+            tx_cancel(&wallet, None, id).and_then(|_| to_json(&EmptyResponse::new()))
+        },
+        error
+    )
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn c_tx_receive(
-    wallet_ptr: usize,
+    wallet_handle: usize,
     account: *const c_char,
     slate_armored: *const c_char,
     error: *mut u8,
 ) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
     unwrap_string_to_c!(
-        tx_receive(
-            &wallet,
-            &cstr_to_rust(account),
-            &cstr_to_rust(slate_armored),
-        ),
+        {
+            ensure_wallet!(wallet_handle, wallet);
+            tx_receive(
+                &wallet,
+                None,
+                &cstr_to_rust(account),
+                &cstr_to_rust(slate_armored),
+            )
+            .and_then(|res| to_json(&res))
+        },
         error
     )
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn c_tx_finalize(
-    wallet_ptr: usize,
+    wallet_handle: usize,
     slate_armored: *const c_char,
     error: *mut u8,
 ) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
-    unwrap_string_to_c!(tx_finalize(&wallet, &cstr_to_rust(slate_armored)), error)
+    unwrap_string_to_c!(
+        {
+            ensure_wallet!(wallet_handle, wallet);
+            tx_finalize(&wallet, None, &cstr_to_rust(slate_armored)).and_then(|res| to_json(&res))
+        },
+        error
+    )
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn c_tx_send_address(
-    wallet_ptr: usize,
+    wallet_handle: usize,
     amount: u64,
     minimum_confirmations: u64,
     selection_strategy_is_use_all: bool,
     address: *const c_char,
     error: *mut u8,
 ) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
     unwrap_string_to_c!(
-        tx_send_address(
-            &wallet,
-            &cstr_to_rust(address),
-            amount,
-            minimum_confirmations,
-            selection_strategy_is_use_all,
-        ),
+        {
+            ensure_wallet!(wallet_handle, wallet, config);
+            tx_send_address(
+                &wallet,
+                None,
+                &config,
+                &cstr_to_rust(address),
+                amount,
+                minimum_confirmations,
+                selection_strategy_is_use_all,
+                None,
+            )
+            .and_then(|res| to_json(&res))
+        },
         error
     )
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn c_tx_post(
-    wallet_ptr: usize,
+    wallet_handle: usize,
     tx_slate_id: *const c_char,
     error: *mut u8,
 ) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
-    unwrap_string_to_c!(tx_post(wallet, &cstr_to_rust(tx_slate_id)), error)
+    unwrap_string_to_c!(
+        {
+            ensure_wallet!(wallet_handle, wallet);
+            tx_post(&wallet, None, &cstr_to_rust(tx_slate_id))
+                .and_then(|_| to_json(&EmptyResponse::new()))
+        },
+        error
+    )
 }
 
 #[no_mangle]
@@ -290,32 +400,37 @@ pub unsafe extern "C" fn c_slatepack_decode(
     slatepack: *const c_char,
     error: *mut u8,
 ) -> *const c_char {
-    unwrap_string_to_c!(slatepack_decode(&cstr_to_rust(slatepack)), error)
+    unwrap_string_to_c!(
+        slatepack_decode(&cstr_to_rust(slatepack)).and_then(|res| to_json(&res)),
+        error
+    )
 }
 
 #[no_mangle]
-pub unsafe extern "C" fn c_get_grin_address(wallet_ptr: usize, error: *mut u8) -> *const c_char {
-    ensure_wallet!(wallet_ptr, wallet, error);
-    unwrap_string_to_c!(get_grin_address(wallet), error)
+pub unsafe extern "C" fn c_get_grin_address(wallet_handle: usize, error: *mut u8) -> *const c_char {
+    unwrap_string_to_c!(
+        {
+            ensure_wallet!(wallet_handle, wallet);
+            get_grin_address(&wallet, None).and_then(|res| to_json(&res))
+        },
+        error
+    )
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn c_start_listen_with_http(
-    wallet_ptr: usize,
+    wallet_handle: usize,
     api_listen_addr: *const c_char,
     error: *mut u8,
 ) -> usize {
-    if (wallet_ptr as *mut Wallet).as_mut().is_none() {
-        *error = 1;
-        return CString::new("Wallet is null".to_owned())
-            .unwrap()
-            .into_raw() as usize;
-    }
-    let wallet = (wallet_ptr as *mut Wallet).as_mut().unwrap();
-    let mut apis = ApiServer::new();
-    let result = start_listen_with_http(&wallet, &cstr_to_rust(api_listen_addr), &mut apis);
-    *error = if result.is_err() { 1 } else { 0 };
-    Box::into_raw(Box::new(apis)) as usize
+    unwrap_to_c!(
+        unmasked_wallet(wallet_handle as Handle).and_then(|opened| {
+            let mut apis = ApiServer::new();
+            start_listen_with_http(&opened.wallet, &cstr_to_rust(api_listen_addr), &mut apis)?;
+            Ok(API_SERVERS.insert(Mutex::new(apis)))
+        }),
+        error
+    )
 }
 
 #[no_mangle]
@@ -323,34 +438,138 @@ pub unsafe extern "C" fn c_stop_listen_with_http(
     api_server: usize,
     error: *mut u8,
 ) -> *const c_char {
-    if let Some(apis) = (api_server as *mut ApiServer).as_mut() {
-        apis.stop();
-        Box::from_raw(api_server as *mut ApiServer);
-        *error = 0;
-        CString::new("".to_owned()).unwrap().into_raw()
-    } else {
-        *error = 1;
-        CString::new("Api server is NULL".to_owned())
-            .unwrap()
-            .into_raw()
-    }
+    unwrap_string_to_c!(
+        API_SERVERS.remove(api_server as Handle).and_then(|apis| {
+            apis.lock().stop();
+            to_json(&EmptyResponse::new())
+        }),
+        error
+    )
 }
 
 #[no_mangle]
 pub unsafe extern "C" fn c_create_tor_config(
-    wallet: usize,
+    wallet_handle: usize,
     listen_addr: *const c_char,
     error: *mut u8,
 ) -> *const c_char {
-    if let Some(wallet) = (wallet as *mut Wallet).as_mut() {
-        unwrap_string_to_c!(
-            create_tor_config(&*wallet, &cstr_to_rust(listen_addr)),
-            error
-        )
-    } else {
-        *error = 1;
-        CString::new("Can not create TOR config, passed wallet is NULL".to_owned())
-            .unwrap()
-            .into_raw()
+    unwrap_string_to_c!(
+        {
+            ensure_wallet!(wallet_handle, wallet, config);
+            create_tor_config(&wallet, None, &config, &cstr_to_rust(listen_addr))
+                .and_then(|_| to_json(&EmptyResponse::new()))
+        },
+        error
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use errors::{ErrorCategory, ErrorResponse};
+    use std::ptr;
+
+    /// Parse the error JSON an export returned and free it
+    unsafe fn take_error(result: *const c_char, error: u8) -> ErrorResponse {
+        assert_eq!(error, 1);
+        let json = CStr::from_ptr(result).to_string_lossy().into_owned();
+        cstr_free(result as *mut c_char);
+        serde_json::from_str(&json).unwrap()
+    }
+
+    /// Take the JSON an export returned on success and free it
+    unsafe fn take_string(result: *const c_char, error: u8) -> String {
+        let json = CStr::from_ptr(result).to_string_lossy().into_owned();
+        cstr_free(result as *mut c_char);
+        assert_eq!(error, 0, "{}", json);
+        json
+    }
+
+    #[test]
+    fn null_strings_return_a_panic_error() {
+        let method = CString::new("seed_new").unwrap();
+        let mut error = 0;
+        let response = unsafe {
+            let result = c_call(0, method.as_ptr(), ptr::null(), &mut error);
+            take_error(result, error)
+        };
+        assert_eq!(response.code, "panic");
+        assert_eq!(response.category, ErrorCategory::Internal);
+        assert!(response.message.contains("NULL string passed"));
+    }
+
+    #[test]
+    fn unknown_handles_return_an_error() {
+        let id = CString::new("0436430c-2b02-624c-2032-570501212b00").unwrap();
+        let empty = CString::new("").unwrap();
+        let method = CString::new("get_grin_address").unwrap();
+        let mut error = 0;
+        let legacy = unsafe {
+            let result = c_tx_post(999_999, id.as_ptr(), &mut error);
+            take_error(result, error)
+        };
+        let dispatched = unsafe {
+            let result = c_call(999_999, method.as_ptr(), empty.as_ptr(), &mut error);
+            take_error(result, error)
+        };
+        // Handles are returned as numbers, the error JSON in their place
+        let listener = unsafe {
+            let result = c_start_listen_with_http(999_999, empty.as_ptr(), &mut error);
+            take_error(result as *const c_char, error)
+        };
+        for response in &[legacy, dispatched, listener] {
+            assert_eq!(response.code, "invalid_handle");
+            assert_eq!(response.category, ErrorCategory::InvalidHandle);
+        }
+    }
+
+    #[test]
+    fn exports_work_on_an_opened_wallet() {
+        let wallet_dir = "target/test_output/ios_exports";
+        let _ = fs::remove_dir_all(wallet_dir);
+        let c = |s: &str| CString::new(s).unwrap();
+        let config = c(&serde_json::json!({
+            "wallet_dir": wallet_dir,
+            "check_node_api_http_addr": "http://127.0.0.1:3413",
+            "chain": "automatedtesting",
+            "account": "default",
+        })
+        .to_string());
+        let password = c("password");
+        let empty = c("");
+        let mut error = 0;
+        unsafe {
+            let (method, params) = (c("seed_new"), c(r#"{"seed_length": 32}"#));
+            let result = c_call(0, method.as_ptr(), params.as_ptr(), &mut error);
+            let json = take_string(result, error);
+            let phrase = c(&serde_json::from_str::<PhraseResponse>(&json)
+                .unwrap()
+                .phrase);
+            let result = c_wallet_init(
+                config.as_ptr(),
+                phrase.as_ptr(),
+                password.as_ptr(),
+                &mut error,
+            );
+            take_string(result, error);
+            let handle = c_open_wallet(config.as_ptr(), password.as_ptr(), &mut error);
+            assert_eq!(error, 0);
+
+            let method = c("get_grin_address");
+            let result = c_call(handle, method.as_ptr(), empty.as_ptr(), &mut error);
+            let dispatched = take_string(result, error);
+            let legacy = take_string(c_get_grin_address(handle, &mut error), error);
+            assert_eq!(dispatched, legacy);
+
+            let id = c(&Uuid::new_v4().to_string());
+            let response = take_error(c_tx_post(handle, id.as_ptr(), &mut error), error);
+            assert_eq!(response.code, "tx_doesnt_exist");
+            assert_eq!(response.category, ErrorCategory::Transaction);
+
+            take_string(c_close_wallet(handle, &mut error), error);
+            let response = take_error(c_get_grin_address(handle, &mut error), error);
+            assert_eq!(response.category, ErrorCategory::InvalidHandle);
+        }
+        let _ = fs::remove_dir_all(wallet_dir);
     }
 }
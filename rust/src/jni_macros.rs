@@ -0,0 +1,138 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// Macros of the JNI exports in `android`. They only call methods on the
+// `JNIEnv` they get, so the tests run them with a stand-in for it.
+macro_rules! unwrap_to_jni (
+    ($env:expr, $func:expr) => (
+        match catch_panic(concat!(file!(), ":", line!()), || $func).and_then(|res| {
+            $env.new_string(res)
+                .map_err(|e| Error::from(ErrorKind::GenericError(e.to_string())))
+        }) {
+            Ok(res) => res.into_inner(),
+            Err(e) => {
+                let _ = $env.throw(e.to_json());
+                std::ptr::null_mut()
+            }
+        }
+    )
+);
+
+macro_rules! unwrap_handle_to_jni (
+    ($env:expr, $func:expr) => (
+        match catch_panic(concat!(file!(), ":", line!()), || $func) {
+            Ok(handle) => handle as jlong,
+            Err(e) => {
+                let _ = $env.throw(e.to_json());
+                0 as jlong
+            }
+        }
+    )
+);
+
+macro_rules! get_string_from_jni (
+    ($str:ident, $env:expr) => (
+        let $str: String = $env
+            .get_string($str)
+            .map_err(|e| ErrorKind::GenericError(format!("Invalid string: {}", e)))?
+            .into();
+    )
+);
+
+#[cfg(test)]
+mod tests {
+    use crate::errors::{catch_panic, Error, ErrorCategory, ErrorKind, ErrorResponse};
+    use crate::handles::{unmasked_wallet, Handle};
+    use std::cell::RefCell;
+
+    #[allow(non_camel_case_types)]
+    type jlong = i64;
+
+    /// Stands in for `JNIEnv`, keeping what was thrown to Java
+    #[derive(Default)]
+    struct FakeEnv {
+        thrown: RefCell<Option<String>>,
+    }
+
+    struct FakeJString(String);
+
+    impl FakeJString {
+        fn into_inner(self) -> *mut String {
+            Box::into_raw(Box::new(self.0))
+        }
+    }
+
+    impl FakeEnv {
+        fn new_string(&self, s: String) -> Result<FakeJString, String> {
+            Ok(FakeJString(s))
+        }
+
+        fn get_string(&self, s: Option<&str>) -> Result<String, String> {
+            s.map(str::to_owned).ok_or_else(|| "null string".to_owned())
+        }
+
+        fn throw(&self, message: String) -> Result<(), ()> {
+            *self.thrown.borrow_mut() = Some(message);
+            Ok(())
+        }
+
+        fn take_error(&self) -> ErrorResponse {
+            let json = self.thrown.borrow_mut().take().expect("nothing thrown");
+            serde_json::from_str(&json).unwrap()
+        }
+    }
+
+    #[test]
+    fn results_are_returned_as_strings() {
+        let env = FakeEnv::default();
+        let method = Some("seed_new");
+        let result = unwrap_to_jni!(env, {
+            get_string_from_jni!(method, env);
+            crate::dispatch::call(0, &method, r#"{"seed_length": 32}"#)
+        });
+        let json = unsafe { Box::from_raw(result) };
+        let response: crate::PhraseResponse = serde_json::from_str(&json).unwrap();
+        assert_eq!(response.phrase.split(' ').count(), 24);
+        assert!(env.thrown.borrow().is_none());
+    }
+
+    #[test]
+    fn errors_are_thrown_as_json() {
+        let env = FakeEnv::default();
+        let method: Option<&str> = None;
+        let result = unwrap_to_jni!(env, {
+            get_string_from_jni!(method, env);
+            crate::dispatch::call(0, &method, "")
+        });
+        assert!(result.is_null());
+        let error = env.take_error();
+        assert_eq!(error.code, "generic");
+        assert!(error.message.contains("Invalid string"));
+
+        let result = unwrap_to_jni!(env, {
+            let opened = unmasked_wallet(999_999)?;
+            Ok(opened.config.wallet_name().to_owned())
+        });
+        assert!(result.is_null());
+        assert_eq!(env.take_error().category, ErrorCategory::InvalidHandle);
+
+        let handle = unwrap_handle_to_jni!(env, {
+            let handles: Vec<Handle> = vec![];
+            Ok(handles[0])
+        });
+        assert_eq!(handle, 0);
+        assert_eq!(env.take_error().code, "panic");
+    }
+}
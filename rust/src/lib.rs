@@ -18,10 +18,13 @@ use futures::channel::oneshot;
 use grin_api::{ApiServer, Router};
 use grin_core::global;
 use grin_core::global::ChainTypes;
-use grin_keychain::{ExtKeychain, Keychain};
+use grin_keychain::{ExtKeychain, Identifier, Keychain};
 use grin_util::file::get_first_line;
+pub use grin_util::secp::key::SecretKey;
+use grin_util::static_secp_instance;
 use grin_util::Mutex;
 use grin_util::ZeroingString;
+use grin_util::{from_hex, to_hex};
 use grin_wallet_api::{self, Foreign, ForeignCheckMiddlewareFn, Owner};
 use grin_wallet_config::types::TorBridgeConfig;
 use grin_wallet_config::types::TorProxyConfig;
@@ -31,10 +34,11 @@ use grin_wallet_impls::tor::config as tor_config;
 use grin_wallet_impls::{
     DefaultLCProvider, DefaultWalletImpl, HTTPNodeClient, HttpSlateSender, SlateSender, WalletSeed,
 };
+use grin_wallet_libwallet::api_impl::owner;
 use grin_wallet_libwallet::{
     address, scan, selection, slate_versions, tx, updater, wallet_lock, NodeClient,
-    NodeVersionInfo, Slate, SlateVersion, SlatepackAddress, SlatepackArmor, Slatepacker,
-    SlatepackerArgs, VersionedSlate, WalletInst, WalletLCProvider,
+    NodeVersionInfo, Slate, SlateState, SlateVersion, SlatepackAddress, SlatepackArmor,
+    Slatepacker, SlatepackerArgs, TxLogEntry, VersionedSlate, WalletInst, WalletLCProvider,
 };
 use grin_wallet_util::OnionV3Address;
 use serde::{Deserialize, Serialize};
@@ -49,41 +53,186 @@ use uuid::Uuid;
 
 #[macro_use]
 extern crate log;
+#[macro_use]
+extern crate lazy_static;
 
-#[cfg(target_os = "android")]
+#[cfg(any(all(target_os = "android", feature = "android"), test))]
+#[macro_use]
+mod jni_macros;
+#[cfg(all(target_os = "android", feature = "android"))]
 #[allow(non_snake_case)]
 mod android;
-#[cfg(target_os = "ios")]
+// Also built for tests, so the C exports are covered by `cargo test`
+#[cfg(any(feature = "ios", test))]
 mod ios;
 
-mod errors;
-use errors::{Error, ErrorKind};
-
-#[derive(Serialize, Deserialize, Clone)]
-struct Config {
-    wallet_dir: String,
-    check_node_api_http_addr: String,
-    chain: String,
-    account: Option<String>,
-}
-
-type Wallet = Arc<
-    Mutex<
-        Box<
-            dyn WalletInst<
-                'static,
-                DefaultLCProvider<'static, HTTPNodeClient, ExtKeychain>,
-                HTTPNodeClient,
-                ExtKeychain,
-            >,
-        >,
-    >,
+pub mod errors;
+pub use errors::{Error, ErrorKind};
+
+pub mod accounts;
+pub use accounts::{
+    account_create, account_rename, accounts_list, tx_transfer_between_accounts,
+    wallet_set_active_account,
+};
+pub mod backup;
+pub use backup::{wallet_backup_export, wallet_backup_import};
+mod counterparty;
+use counterparty::{counterparty_addresses, save_counterparty};
+pub mod dispatch;
+pub mod fees;
+pub use fees::tx_fee_estimate;
+pub mod handles;
+pub mod invoice;
+pub use invoice::{tx_issue_invoice, tx_process_invoice};
+pub mod mnemonic;
+pub use mnemonic::mnemonic_validate;
+pub mod payment_proof;
+pub use payment_proof::{payment_proof_export, payment_proof_verify};
+pub mod responses;
+pub use responses::*;
+
+/// Name of the wallet stored directly in `wallet_dir`
+pub const DEFAULT_WALLET_NAME: &str = "default";
+/// Account used when the config doesn't name one
+pub const DEFAULT_ACCOUNT: &str = "default";
+/// Subdirectory of `wallet_dir` holding the other named wallets
+const NAMED_WALLETS_DIR: &str = "wallets";
+const SEED_FILE: &str = "wallet.seed";
+/// Copy of the seed kept while its password is changed
+const ROLLBACK_SEED_FILE: &str = "wallet.seed.rollback";
+/// Directories of a data dir which belong to the wallet in it
+const WALLET_DIRS: [&str; 2] = [GRIN_WALLET_DIR, "tor"];
+/// `Slatepack::mode` of a slatepack with an encrypted payload
+const SLATEPACK_MODE_ENCRYPTED: u8 = 1;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Config {
+    pub wallet_dir: String,
+    /// Wallet inside `wallet_dir` to use, the default wallet if not set
+    #[serde(default)]
+    pub wallet_name: Option<String>,
+    pub check_node_api_http_addr: String,
+    pub chain: String,
+    pub account: Option<String>,
+    /// Minimum fee base accepted for transactions, in nanogrin per weight unit
+    #[serde(default = "Config::default_accept_fee_base")]
+    pub accept_fee_base: u64,
+    /// Port of the foreign API listener
+    #[serde(default = "Config::default_api_listen_port")]
+    pub api_listen_port: u16,
+    /// Maximum number of inputs selected for a transaction
+    #[serde(default = "Config::default_max_outputs")]
+    pub max_outputs: usize,
+    /// Number of change outputs created for a transaction
+    #[serde(default = "Config::default_num_change_outputs")]
+    pub num_change_outputs: usize,
+    /// Tor SOCKS proxy used to send to Slatepack addresses
+    #[serde(default = "Config::default_tor_socks_addr")]
+    pub tor_socks_addr: String,
+    /// Seconds without calls on the opened wallet after which it is locked,
+    /// it stays unlocked until closed if not set
+    #[serde(default)]
+    pub auto_lock_timeout: Option<u64>,
+}
+
+/// Wallet backed by the given node client
+pub type WalletInstance<C> = Arc<
+    Mutex<Box<dyn WalletInst<'static, DefaultLCProvider<'static, C, ExtKeychain>, C, ExtKeychain>>>,
 >;
 
+/// Wallet talking to a node over its HTTP API, as used by the app
+pub type Wallet = WalletInstance<HTTPNodeClient>;
+
 impl Config {
-    fn from_str(json: &str) -> Result<Self, Error> {
-        serde_json::from_str::<Config>(json)
-            .map_err(|e| Error::from(ErrorKind::ConfigCannotBeParsed(e.to_string())))
+    pub fn from_str(json: &str) -> Result<Self, Error> {
+        let config = serde_json::from_str::<Config>(json)
+            .map_err(|e| Error::from(ErrorKind::ConfigCannotBeParsed(e.to_string())))?;
+        config.validate()?;
+        Ok(config)
+    }
+
+    fn validate(&self) -> Result<(), Error> {
+        let error = if self.accept_fee_base == 0 {
+            Some("accept_fee_base must be greater than 0".to_owned())
+        } else if self.api_listen_port == 0 {
+            Some("api_listen_port must be greater than 0".to_owned())
+        } else if self.max_outputs == 0 {
+            Some("max_outputs must be greater than 0".to_owned())
+        } else if self.num_change_outputs == 0 {
+            Some("num_change_outputs must be greater than 0".to_owned())
+        } else if self.auto_lock_timeout == Some(0) {
+            Some("auto_lock_timeout must be greater than 0".to_owned())
+        } else if self.tor_socks_addr.parse::<SocketAddr>().is_err() {
+            Some(format!(
+                "tor_socks_addr {} is not a valid socket address",
+                self.tor_socks_addr
+            ))
+        } else {
+            None
+        };
+        if let Some(e) = error {
+            return Err(ErrorKind::ConfigCannotBeParsed(e).into());
+        }
+        match &self.wallet_name {
+            Some(name) => check_wallet_name(name),
+            None => Ok(()),
+        }
+    }
+
+    /// Name of the wallet this config points to
+    pub fn wallet_name(&self) -> &str {
+        self.wallet_name
+            .as_ref()
+            .map(String::as_str)
+            .unwrap_or(DEFAULT_WALLET_NAME)
+    }
+
+    /// Top level directory of the wallet this config points to. The default
+    /// wallet lives in `wallet_dir` itself, so existing installs keep working.
+    pub fn data_dir(&self) -> String {
+        named_wallet_dir(&self.wallet_dir, self.wallet_name())
+    }
+
+    fn default_accept_fee_base() -> u64 {
+        500000
+    }
+
+    fn default_api_listen_port() -> u16 {
+        3415
+    }
+
+    fn default_max_outputs() -> usize {
+        500
+    }
+
+    fn default_num_change_outputs() -> usize {
+        1
+    }
+
+    fn default_tor_socks_addr() -> String {
+        "127.0.0.1:39059".to_owned()
+    }
+}
+
+fn named_wallet_dir(wallet_dir: &str, name: &str) -> String {
+    if name == DEFAULT_WALLET_NAME {
+        wallet_dir.to_owned()
+    } else {
+        format!("{}/{}/{}", wallet_dir, NAMED_WALLETS_DIR, name)
+    }
+}
+
+/// Wallet names become directory names, so only allow a safe subset
+fn check_wallet_name(name: &str) -> Result<(), Error> {
+    let valid = !name.is_empty()
+        && name.len() <= 64
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(ErrorKind::InvalidWalletName(name.to_owned()).into())
     }
 }
 
@@ -97,10 +246,11 @@ fn create_wallet_config(config: Config) -> Result<WalletConfig, Error> {
     };
 
     let api_secret_path = config.wallet_dir.clone() + "/.api_secret";
+    let data_file_dir = config.data_dir();
     Ok(WalletConfig {
-        accept_fee_base: Some(500000),
+        accept_fee_base: Some(config.accept_fee_base),
         chain_type: Some(chain_type),
-        api_listen_port: 3415,
+        api_listen_port: config.api_listen_port,
         api_secret_path: None,
         node_api_secret_path: if Path::new(&api_secret_path).exists() {
             Some(api_secret_path)
@@ -108,7 +258,7 @@ fn create_wallet_config(config: Config) -> Result<WalletConfig, Error> {
             None
         },
         check_node_api_http_addr: config.check_node_api_http_addr,
-        data_file_dir: config.wallet_dir,
+        data_file_dir,
         tls_certificate_file: None,
         tls_certificate_key: None,
         dark_background_color_scheme: Some(true),
@@ -135,7 +285,19 @@ where
     Ok(Arc::new(Mutex::new(wallet)))
 }
 
-fn get_wallet(config: &Config) -> Result<Wallet, Error> {
+fn http_node_client(config: &Config) -> Result<HTTPNodeClient, Error> {
+    let wallet_config = create_wallet_config(config.clone())?;
+    let node_api_secret = get_first_line(wallet_config.node_api_secret_path.clone());
+    Ok(HTTPNodeClient::new(
+        &wallet_config.check_node_api_http_addr,
+        node_api_secret,
+    )?)
+}
+
+fn get_wallet<C: NodeClient + 'static>(
+    config: &Config,
+    node_client: C,
+) -> Result<WalletInstance<C>, Error> {
     let wallet_config = create_wallet_config(config.clone())?;
     let target_chaintype = wallet_config.chain_type.unwrap_or(ChainTypes::Mainnet);
     if !global::GLOBAL_CHAIN_TYPE.is_init() {
@@ -146,44 +308,113 @@ fn get_wallet(config: &Config) -> Result<Wallet, Error> {
     };
 
     warn!("Chaintype: {:?}", global::get_chain_type());
-    let node_api_secret = get_first_line(wallet_config.node_api_secret_path.clone());
-
-    let node_client =
-        HTTPNodeClient::new(&wallet_config.check_node_api_http_addr, node_api_secret)?;
-    let wallet = inst_wallet::<
-        DefaultLCProvider<HTTPNodeClient, ExtKeychain>,
-        HTTPNodeClient,
-        ExtKeychain,
-    >(wallet_config.clone(), node_client)?;
+    let accept_fee_base = config.accept_fee_base;
+    if !global::GLOBAL_ACCEPT_FEE_BASE.is_init() {
+        global::init_global_accept_fee_base(accept_fee_base)
+    };
+    if global::get_accept_fee_base() != accept_fee_base {
+        global::set_local_accept_fee_base(accept_fee_base);
+    };
+    let wallet = inst_wallet::<DefaultLCProvider<C, ExtKeychain>, C, ExtKeychain>(
+        wallet_config,
+        node_client,
+    )?;
     return Ok(wallet);
 }
 
-fn open_wallet(config_json: &str, password: &str) -> Result<Wallet, Error> {
+pub fn open_wallet(config_json: &str, password: &str) -> Result<Wallet, Error> {
     let config = Config::from_str(config_json)?;
-    let wallet = get_wallet(&config)?;
-    let mut opened = false;
-    {
+    let node_client = http_node_client(&config)?;
+    open_wallet_with_client(config_json, password, node_client)
+}
+
+/// Same as `open_wallet`, but talks to the node through `node_client`
+pub fn open_wallet_with_client<C: NodeClient + 'static>(
+    config_json: &str,
+    password: &str,
+    node_client: C,
+) -> Result<WalletInstance<C>, Error> {
+    let (wallet, _) = open(config_json, password, node_client, false)?;
+    Ok(wallet)
+}
+
+/// Open the wallet with its keychain XORed with a random mask. The keychain
+/// is unusable without the returned mask, which has to be passed to every
+/// call touching keys; the caller should keep it in memory only.
+pub fn open_wallet_masked(config_json: &str, password: &str) -> Result<(Wallet, SecretKey), Error> {
+    let config = Config::from_str(config_json)?;
+    let node_client = http_node_client(&config)?;
+    open_wallet_masked_with_client(config_json, password, node_client)
+}
+
+/// Same as `open_wallet_masked`, but talks to the node through `node_client`
+pub fn open_wallet_masked_with_client<C: NodeClient + 'static>(
+    config_json: &str,
+    password: &str,
+    node_client: C,
+) -> Result<(WalletInstance<C>, SecretKey), Error> {
+    let (wallet, mask) = open(config_json, password, node_client, true)?;
+    let mask = mask.ok_or_else(|| ErrorKind::GenericError("No keychain mask".to_owned()))?;
+    Ok((wallet, mask))
+}
+
+fn open<C: NodeClient + 'static>(
+    config_json: &str,
+    password: &str,
+    node_client: C,
+    use_mask: bool,
+) -> Result<(WalletInstance<C>, Option<SecretKey>), Error> {
+    let config = Config::from_str(config_json)?;
+    let seed_dir = format!("{}/{}", config.data_dir(), GRIN_WALLET_DIR);
+    if !wallet_exists_in(&config.data_dir()) {
+        return Err(ErrorKind::WalletDoesntExist().into());
+    }
+    // The lifecycle provider reports every seed failure as the same message,
+    // so decrypt the seed first to tell a wrong password from a broken file
+    if let Err(e) = WalletSeed::from_file(&seed_dir, ZeroingString::from(password)) {
+        return Err(match e {
+            grin_wallet_impls::Error::Encryption => ErrorKind::WrongPassword(),
+            grin_wallet_impls::Error::WalletSeedDoesntExist => ErrorKind::WalletDoesntExist(),
+            e => ErrorKind::WalletDataCorrupted(e.to_string()),
+        }
+        .into());
+    }
+
+    let wallet = get_wallet(&config, node_client)?;
+    let mask = {
         let mut wallet_lock = wallet.lock();
         let lc = wallet_lock.lc_provider()?;
-        if let Ok(open_wallet) = lc.wallet_exists(None) {
-            if open_wallet {
-                lc.open_wallet(None, ZeroingString::from(password), false, false)?;
-                let wallet_inst = lc.wallet_inst()?;
-                if let Some(account) = config.account {
-                    wallet_inst.set_parent_key_id_by_name(&account)?;
-                    opened = true;
-                }
-            }
+        let name = Some(config.wallet_name());
+        let mask = lc
+            .open_wallet(name, ZeroingString::from(password), use_mask, false)
+            .map_err(|e| ErrorKind::WalletDataCorrupted(e.to_string()))?;
+        let account = config
+            .account
+            .clone()
+            .unwrap_or_else(|| DEFAULT_ACCOUNT.to_owned());
+        let selected = lc.wallet_inst()?.set_parent_key_id_by_name(&account);
+        if selected.is_err() {
+            lc.close_wallet(name)?;
+            return Err(ErrorKind::AccountNotFound(account).into());
         }
-    }
-    if opened {
-        Ok(wallet)
-    } else {
-        Err(Error::from(ErrorKind::WalletDoesntExist()))
-    }
+        mask
+    };
+    Ok((wallet, mask))
 }
 
-fn close_wallet(wallet: &Wallet) -> Result<String, Error> {
+/// Hex encoding of a keychain mask, as handed to the app
+pub fn keychain_mask_to_hex(mask: &SecretKey) -> String {
+    to_hex(&mask.0)
+}
+
+pub fn keychain_mask_from_hex(mask_hex: &str) -> Result<SecretKey, Error> {
+    let bytes = from_hex(mask_hex).map_err(|_| ErrorKind::InvalidKeychainMask())?;
+    let secp = static_secp_instance();
+    let secp = secp.lock();
+    SecretKey::from_slice(&secp, &bytes).map_err(|_| ErrorKind::InvalidKeychainMask().into())
+}
+
+pub fn close_wallet<C: NodeClient + 'static>(wallet: &WalletInstance<C>) -> Result<(), Error> {
     let mut wallet_lock = wallet.lock();
     let lc = wallet_lock.lc_provider()?;
     if let Ok(open_wallet) = lc.wallet_exists(None) {
@@ -191,20 +422,34 @@ fn close_wallet(wallet: &Wallet) -> Result<String, Error> {
             lc.close_wallet(None)?;
         }
     }
-    Ok("Wallet has been closed".to_owned())
+    Ok(())
 }
 
-fn seed_new(seed_length: usize) -> Result<String, Error> {
-    Ok(WalletSeed::init_new(seed_length, false, None).to_mnemonic()?)
+pub fn seed_new(seed_length: usize) -> Result<PhraseResponse, Error> {
+    let phrase = WalletSeed::init_new(seed_length, false, None).to_mnemonic()?;
+    Ok(PhraseResponse::new(phrase))
 }
 
-fn wallet_init(config_json: &str, phrase: &str, password: &str) -> Result<String, Error> {
+pub fn wallet_init(config_json: &str, phrase: &str, password: &str) -> Result<(), Error> {
     let config = Config::from_str(config_json)?;
-    let wallet = get_wallet(&config)?;
+    let node_client = http_node_client(&config)?;
+    wallet_init_with_client(config_json, phrase, password, node_client)
+}
+
+/// Same as `wallet_init`, but talks to the node through `node_client`
+pub fn wallet_init_with_client<C: NodeClient + 'static>(
+    config_json: &str,
+    phrase: &str,
+    password: &str,
+    node_client: C,
+) -> Result<(), Error> {
+    let config = Config::from_str(config_json)?;
+    let phrase = mnemonic::check_mnemonic(phrase)?;
+    let wallet = get_wallet(&config, node_client)?;
     let mut wallet_lock = wallet.lock();
     let lc = wallet_lock.lc_provider()?;
     lc.create_wallet(
-        None,
+        Some(config.wallet_name()),
         Some(ZeroingString::from(phrase)),
         32,
         ZeroingString::from(password),
@@ -212,17 +457,132 @@ fn wallet_init(config_json: &str, phrase: &str, password: &str) -> Result<String
         true,
     )?;
 
-    Ok("".to_owned())
+    Ok(())
 }
 
-fn wallet_scan_outputs(
-    wallet: &Wallet,
+/// Re-encrypt the wallet seed with `new_password`. The seed is copied aside
+/// first and put back if the lifecycle provider fails midway, in which case
+/// the copy is kept as well.
+pub fn wallet_change_password(
+    config_json: &str,
+    old_password: &str,
+    new_password: &str,
+) -> Result<(), Error> {
+    let config = Config::from_str(config_json)?;
+    let seed_dir = Path::new(&config.data_dir()).join(GRIN_WALLET_DIR);
+    let seed_path = seed_dir.join(SEED_FILE);
+    if !seed_path.is_file() {
+        return Err(ErrorKind::WalletDoesntExist().into());
+    }
+    // Refuse a wrong password before touching any file
+    WalletSeed::from_file(
+        &seed_dir.to_string_lossy(),
+        ZeroingString::from(old_password),
+    )?;
+
+    let rollback_path = seed_dir.join(ROLLBACK_SEED_FILE);
+    fs::copy(&seed_path, &rollback_path).context(ErrorKind::IO)?;
+    let node_client = http_node_client(&config)?;
+    let wallet = get_wallet(&config, node_client)?;
+    let result = (|| -> Result<(), Error> {
+        let mut wallet_lock = wallet.lock();
+        let lc = wallet_lock.lc_provider()?;
+        lc.change_password(
+            Some(config.wallet_name()),
+            ZeroingString::from(old_password),
+            ZeroingString::from(new_password),
+        )?;
+        Ok(())
+    })();
+    match result {
+        Ok(()) => {
+            // The password has been changed, a copy left behind is only stale
+            if let Err(e) = fs::remove_file(&rollback_path) {
+                warn!("Failed to remove {}: {}", rollback_path.display(), e);
+            }
+            Ok(())
+        }
+        Err(e) => {
+            error!("Password change failed, restoring the seed: {}", e);
+            // Copy next to the seed and rename over it, so the seed file is
+            // replaced atomically
+            let tmp_path = seed_path.with_extension("seed.tmp");
+            fs::copy(&rollback_path, &tmp_path).context(ErrorKind::IO)?;
+            fs::rename(&tmp_path, &seed_path).context(ErrorKind::IO)?;
+            Err(e)
+        }
+    }
+}
+
+/// Names of the wallets created in `wallet_dir`, the default wallet first
+pub fn wallets_list(wallet_dir: &str) -> Result<WalletsListResponse, Error> {
+    let mut wallets = vec![];
+    if wallet_exists_in(wallet_dir) {
+        wallets.push(DEFAULT_WALLET_NAME.to_owned());
+    }
+    let named_dir = Path::new(wallet_dir).join(NAMED_WALLETS_DIR);
+    if named_dir.is_dir() {
+        let mut named = vec![];
+        for entry in fs::read_dir(&named_dir).context(ErrorKind::IO)? {
+            let entry = entry.context(ErrorKind::IO)?;
+            if let Some(name) = entry.file_name().to_str() {
+                if check_wallet_name(name).is_ok()
+                    && name != DEFAULT_WALLET_NAME
+                    && wallet_exists_in(&named_wallet_dir(wallet_dir, name))
+                {
+                    named.push(name.to_owned());
+                }
+            }
+        }
+        named.sort();
+        wallets.extend(named);
+    }
+    Ok(WalletsListResponse::new(wallets))
+}
+
+fn wallet_exists_in(data_dir: &str) -> bool {
+    Path::new(data_dir)
+        .join(GRIN_WALLET_DIR)
+        .join(SEED_FILE)
+        .is_file()
+}
+
+/// Delete the wallet the config points to. The password is checked first,
+/// so a wallet can't be wiped by someone who merely holds the device.
+/// Other wallets in `wallet_dir` are left untouched.
+pub fn wallet_delete(config_json: &str, password: &str) -> Result<(), Error> {
+    let config = Config::from_str(config_json)?;
+    let data_dir = config.data_dir();
+    if !wallet_exists_in(&data_dir) {
+        return Err(ErrorKind::WalletDoesntExist().into());
+    }
+    WalletSeed::from_file(
+        &format!("{}/{}", data_dir, GRIN_WALLET_DIR),
+        ZeroingString::from(password),
+    )?;
+    if config.wallet_name() == DEFAULT_WALLET_NAME {
+        for dir in &WALLET_DIRS {
+            let path = Path::new(&data_dir).join(dir);
+            if path.exists() {
+                fs::remove_dir_all(path).context(ErrorKind::IO)?;
+            }
+        }
+    } else {
+        fs::remove_dir_all(&data_dir).context(ErrorKind::IO)?;
+    }
+    Ok(())
+}
+
+/// Scan outputs in the given PMMR range, returns last retrieved PMMR index
+pub fn wallet_scan_outputs<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
     last_retrieved_index: u64,
     highest_index: u64,
-) -> Result<String, Error> {
+) -> Result<WalletScanOutputsResponse, Error> {
     let info = scan(
         wallet.clone(),
-        None,
+        keychain_mask,
         false,
         (last_retrieved_index, highest_index),
         &None,
@@ -235,37 +595,49 @@ fn wallet_scan_outputs(
     };
     {
         wallet_lock!(wallet, w);
-        let mut batch = w.batch(None)?;
+        let mut batch = w.batch(keychain_mask)?;
         batch.save_last_confirmed_height(&parent_key_id, info.height)?;
         batch.commit()?;
     };
 
-    Ok(serde_json::to_string(&result).unwrap())
+    Ok(WalletScanOutputsResponse::new(result))
 }
 
-fn wallet_pmmr_range(wallet: &Wallet) -> Result<String, Error> {
+pub fn wallet_pmmr_range<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+) -> Result<WalletPmmrRangeResponse, Error> {
     wallet_lock!(wallet, w);
-    let pmmr_range = w.w2n_client().height_range_to_pmmr_indices(0, None)?;
-    Ok(serde_json::to_string(&pmmr_range).unwrap())
+    let (lowest_index, highest_index) = w.w2n_client().height_range_to_pmmr_indices(0, None)?;
+    Ok(WalletPmmrRangeResponse::new(lowest_index, highest_index))
 }
 
-fn wallet_phrase(wallet_dir: &str, password: &str) -> Result<String, Error> {
+/// Recovery phrase of the wallet in `wallet_dir`, which is `Config::data_dir`
+/// for a named wallet
+pub fn wallet_phrase(wallet_dir: &str, password: &str) -> Result<PhraseResponse, Error> {
     let seed = WalletSeed::from_file(
         &format!("{}/{}", wallet_dir, GRIN_WALLET_DIR),
         ZeroingString::from(password),
     )?;
-    Ok(seed.to_mnemonic()?)
+    Ok(PhraseResponse::new(seed.to_mnemonic()?))
 }
 
-fn tx_get(wallet: &Wallet, refresh_from_node: bool, tx_slate_id: &str) -> Result<String, Error> {
+pub fn tx_get<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    refresh_from_node: bool,
+    tx_slate_id: &str,
+) -> Result<TxGetResponse, Error> {
     let api = Owner::new(wallet.clone(), None);
     let uuid = Uuid::parse_str(tx_slate_id).map_err(|e| ErrorKind::GenericError(e.to_string()))?;
-    let txs = api.retrieve_txs(None, refresh_from_node, None, Some(uuid), None)?;
-    Ok(serde_json::to_string(&txs).unwrap())
+    let (refreshed, txs) =
+        api.retrieve_txs(keychain_mask, refresh_from_node, None, Some(uuid), None)?;
+    let counterparty_addresses = counterparty_addresses(wallet, &txs)?;
+    Ok(TxGetResponse::new(refreshed, txs, counterparty_addresses))
 }
 
 fn update_state<'a, L, C, K>(
     wallet_inst: Arc<Mutex<Box<dyn WalletInst<'a, L, C, K>>>>,
+    keychain_mask: Option<&SecretKey>,
 ) -> Result<bool, Error>
 where
     L: WalletLCProvider<'a, C, K>,
@@ -286,7 +658,13 @@ where
 
     {
         wallet_lock!(wallet_inst, w);
-        if !match updater::refresh_output_state(&mut **w, None, tip.0, &parent_key_id, true) {
+        if !match updater::refresh_output_state(
+            &mut **w,
+            keychain_mask,
+            tip.0,
+            &parent_key_id,
+            true,
+        ) {
             Ok(_) => true,
             Err(_) => false,
         } {
@@ -306,7 +684,7 @@ where
             if tip.0 >= e {
                 wallet_lock!(wallet_inst, w);
                 let parent_key_id = w.parent_key_id();
-                tx::cancel_tx(&mut **w, None, &parent_key_id, Some(tx.id), None)?;
+                tx::cancel_tx(&mut **w, keychain_mask, &parent_key_id, Some(tx.id), None)?;
                 continue;
             }
         }
@@ -326,7 +704,7 @@ where
             if let Some(k) = kernel {
                 debug!("Kernel Retrieved: {:?}", k);
                 wallet_lock!(wallet_inst, w);
-                let mut batch = w.batch(None)?;
+                let mut batch = w.batch(keychain_mask)?;
                 tx.confirmed = true;
                 tx.update_confirmation_ts();
                 batch.save_tx_log_entry(tx.clone(), &parent_key_id)?;
@@ -338,12 +716,14 @@ where
     return Ok(true);
 }
 
-fn txs_get(
-    wallet: &Wallet,
+pub fn txs_get<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
     minimum_confirmations: u64,
     refresh_from_node: bool,
-) -> Result<String, Error> {
-    let refreshed = refresh_from_node && update_state(wallet.clone()).unwrap_or(false);
+) -> Result<TxsGetResponse, Error> {
+    let refreshed =
+        refresh_from_node && update_state(wallet.clone(), keychain_mask).unwrap_or(false);
     let wallet_info = {
         wallet_lock!(wallet, w);
         let parent_key_id = w.parent_key_id();
@@ -351,40 +731,51 @@ fn txs_get(
     };
     let api = Owner::new(wallet.clone(), None);
 
-    let txs = api.retrieve_txs(None, false, None, None, None)?;
-    let result = (refreshed, txs.1, wallet_info);
-    Ok(serde_json::to_string(&result).unwrap())
+    let txs = api.retrieve_txs(keychain_mask, false, None, None, None)?;
+    let counterparty_addresses = counterparty_addresses(wallet, &txs.1)?;
+    Ok(TxsGetResponse::new(
+        refreshed,
+        txs.1,
+        wallet_info,
+        counterparty_addresses,
+    ))
 }
 
 #[derive(Serialize, Deserialize)]
-struct Strategy {
-    selection_strategy_is_use_all: bool,
-    total: u64,
-    fee: u64,
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct Strategy {
+    pub selection_strategy_is_use_all: bool,
+    pub total: u64,
+    pub fee: u64,
 }
 
-fn tx_strategies(
-    wallet: &Wallet,
+pub fn tx_strategies<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    config: &Config,
     amount: u64,
     minimum_confirmations: u64,
-) -> Result<String, Error> {
+) -> Result<TxStrategiesResponse, Error> {
     let mut result = vec![];
     wallet_lock!(wallet, w);
     let parent_key_id = w.parent_key_id().clone();
     let client = w.w2n_client().clone();
     let tip = client.get_chain_tip()?;
     for selection_strategy_is_use_all in vec![true, false].into_iter() {
-        if let Ok((_coins, total, _amount, fee)) = selection::select_coins_and_fee(
-            &mut **w,
-            amount,
-            false,
-            tip.0,
-            minimum_confirmations,
-            500,
-            1,
-            selection_strategy_is_use_all,
-            &parent_key_id,
-        ) {
+        if let Ok((_coins, total, _amount, fee)) =
+            fees::with_fee_base(config.accept_fee_base, || {
+                selection::select_coins_and_fee(
+                    &mut **w,
+                    amount,
+                    false,
+                    tip.0,
+                    minimum_confirmations,
+                    config.max_outputs,
+                    config.num_change_outputs,
+                    selection_strategy_is_use_all,
+                    &parent_key_id,
+                )
+            })
+        {
             result.push(Strategy {
                 selection_strategy_is_use_all,
                 total,
@@ -392,74 +783,100 @@ fn tx_strategies(
             })
         }
     }
-    Ok(serde_json::to_string(&result).unwrap())
+    Ok(TxStrategiesResponse::new(result))
 }
 
-fn tx_create(
-    wallet: &Wallet,
+/// Create a new transaction, returns its tx log entries and armored slatepack.
+/// With the Slatepack address of the recipient the slatepack is encrypted to
+/// it and the recipient is asked for a payment proof. A `fee_base` above the
+/// configured one pays a higher fee.
+#[allow(clippy::too_many_arguments)]
+pub fn tx_create<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    config: &Config,
     amount: u64,
     minimum_confirmations: u64,
     selection_strategy_is_use_all: bool,
-) -> Result<String, Error> {
+    recipient_address: Option<&str>,
+    fee_base: Option<u64>,
+) -> Result<TxCreateResponse, Error> {
+    let fee_base = fees::effective_fee_base(config, fee_base)?;
+    let recipient = match recipient_address {
+        Some(address) => Some(SlatepackAddress::try_from(address)?),
+        None => None,
+    };
     let parent_key_id = {
         wallet_lock!(wallet, w);
         w.parent_key_id().clone()
     };
 
-    let slate = {
+    let (slate, sender) = {
         wallet_lock!(wallet, w);
         let mut slate = tx::new_tx_slate(&mut **w, amount, false, 2, false, None)?;
         let height = w.w2n_client().get_chain_tip()?.0;
 
-        let context = tx::add_inputs_to_slate(
-            &mut **w,
-            None,
-            &mut slate,
-            height,
-            minimum_confirmations,
-            500,
-            1,
-            selection_strategy_is_use_all,
-            &parent_key_id,
-            true,
-            false,
-            false,
-        )?;
+        let mut context = fees::with_fee_base(fee_base, || {
+            tx::add_inputs_to_slate(
+                &mut **w,
+                keychain_mask,
+                &mut slate,
+                height,
+                minimum_confirmations,
+                config.max_outputs,
+                config.num_change_outputs,
+                selection_strategy_is_use_all,
+                &parent_key_id,
+                true,
+                false,
+                false,
+            )
+        })?;
+        let keychain = w.keychain(keychain_mask)?;
+        if let Some(recipient) = &recipient {
+            payment_proof::add_payment_proof(
+                &keychain,
+                &parent_key_id,
+                &mut slate,
+                &mut context,
+                recipient,
+            )?;
+        }
+        // Our address tells the recipient who pays and lets it encrypt its
+        // reply
+        let sender = slatepack_address(&keychain, &parent_key_id)?;
 
         {
-            let mut batch = w.batch(None)?;
+            let mut batch = w.batch(keychain_mask)?;
             batch.save_private_context(slate.id.as_bytes(), &context)?;
             batch.commit()?;
         }
 
         // slate.version_info.version = 2;
         // slate.version_info.orig_version = 2;
-        selection::lock_tx_context(&mut **w, None, &slate, height, &context, None)?;
+        selection::lock_tx_context(&mut **w, keychain_mask, &slate, height, &context, None)?;
         slate.compact()?;
-        slate
+        (slate, sender)
     };
 
-    let packer = Slatepacker::new(SlatepackerArgs {
-        sender: None, // sender
-        recipients: vec![],
-        dec_key: None,
-    });
-    let slatepack = packer.create_slatepack(&slate)?;
+    if let Some(recipient) = &recipient {
+        save_counterparty(wallet, slate.id, recipient)?;
+    }
+    let slatepack = encode_slatepack(&slate, Some(sender), recipient)?;
     let api = Owner::new(wallet.clone(), None);
-    let txs = api.retrieve_txs(None, false, None, Some(slate.id), None)?;
-    let result = (
-        txs.1,
-        SlatepackArmor::encode(&slatepack).map_err(|e| ErrorKind::GenericError(e.to_string()))?,
-    );
-
-    Ok(serde_json::to_string(&result).map_err(|e| ErrorKind::GenericError(e.to_string()))?)
+    let txs = api.retrieve_txs(keychain_mask, false, None, Some(slate.id), None)?;
+    Ok(TxCreateResponse::new(txs.1, slatepack))
 }
 
-fn tx_cancel(wallet: &Wallet, id: u32) -> Result<String, Error> {
+pub fn tx_cancel<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    id: u32,
+) -> Result<(), Error> {
     wallet_lock!(wallet, w);
     let parent_key_id = w.parent_key_id();
-    tx::cancel_tx(&mut **w, None, &parent_key_id, Some(id), None)?;
-    Ok("".to_owned())
+    tx::cancel_tx(&mut **w, keychain_mask, &parent_key_id, Some(id), None)?;
+    Ok(())
 }
 
 fn check_middleware(
@@ -492,53 +909,82 @@ fn check_middleware(
     }
 }
 
-fn tx_receive(wallet: &Wallet, account: &str, slate_armored: &str) -> Result<String, Error> {
-    let foreign_api = Foreign::new(wallet.clone(), None, Some(check_middleware), false);
+/// Receive a slatepack, returns tx log entries and armored response slatepack
+pub fn tx_receive<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    account: &str,
+    slate_armored: &str,
+) -> Result<TxReceiveResponse, Error> {
+    let foreign_api = Foreign::new(
+        wallet.clone(),
+        keychain_mask.cloned(),
+        Some(check_middleware),
+        false,
+    );
     let owner_api = Owner::new(wallet.clone(), None);
 
-    let mut slate =
-        owner_api.slate_from_slatepack_message(None, slate_armored.to_owned(), vec![0])?;
-    let slatepack = owner_api.decode_slatepack_message(None, slate_armored.to_owned(), vec![0])?;
+    let (slate, sender) = decode_slatepack(wallet, keychain_mask, slate_armored, Some(account))?;
 
-    let _ret_address = slatepack.sender;
-
-    slate = foreign_api.receive_tx(&slate, Some(&account), None)?;
-    let txs = owner_api.retrieve_txs(None, false, None, Some(slate.id), None)?;
-    let packer = Slatepacker::new(SlatepackerArgs {
-        sender: None, // sender
-        recipients: vec![],
-        dec_key: None,
-    });
-    let slatepack = packer.create_slatepack(&slate)?;
-    let result = (
-        txs.1,
-        SlatepackArmor::encode(&slatepack).map_err(|e| ErrorKind::GenericError(e.to_string()))?,
-    );
-
-    Ok(serde_json::to_string(&result).map_err(|e| ErrorKind::GenericError(e.to_string()))?)
+    let slate = foreign_api.receive_tx(&slate, Some(&account), None)?;
+    if let Some(sender) = &sender {
+        save_counterparty(wallet, slate.id, sender)?;
+    }
+    let txs = slate_txs(wallet, slate.id)?;
+    let own_address = account_slatepack_address(wallet, keychain_mask, Some(account))?;
+    // Only the sender can read the reply if it told its address
+    Ok(TxReceiveResponse::new(
+        txs,
+        encode_slatepack(&slate, Some(own_address), sender.clone())?,
+        sender.map(|address| address.to_string()),
+    ))
 }
 
-fn tx_finalize(wallet: &Wallet, slate_armored: &str) -> Result<String, Error> {
+pub fn tx_finalize<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    slate_armored: &str,
+) -> Result<TxFinalizeResponse, Error> {
     let owner_api = Owner::new(wallet.clone(), None);
-    let mut slate =
-        owner_api.slate_from_slatepack_message(None, slate_armored.to_owned(), vec![0])?;
-    let slatepack = owner_api.decode_slatepack_message(None, slate_armored.to_owned(), vec![0])?;
-
-    let _ret_address = slatepack.sender;
-
-    slate = owner_api.finalize_tx(None, &slate)?;
-    let txs = owner_api.retrieve_txs(None, false, None, Some(slate.id), None)?;
-
-    Ok(serde_json::to_string(&txs.1).map_err(|e| ErrorKind::GenericError(e.to_string()))?)
+    // The reply is encrypted to the account which sent or issued the slate
+    let (slate, sender) = decode_slatepack(wallet, keychain_mask, slate_armored, None)?;
+
+    // A paid invoice is finalized by its issuer through the foreign API
+    let slate = if slate.state == SlateState::Invoice2 {
+        let foreign_api = Foreign::new(
+            wallet.clone(),
+            keychain_mask.cloned(),
+            Some(check_middleware),
+            false,
+        );
+        foreign_api.finalize_tx(&slate, false)?
+    } else {
+        owner_api.finalize_tx(keychain_mask, &slate)?
+    };
+    if let Some(sender) = &sender {
+        save_counterparty(wallet, slate.id, sender)?;
+    }
+    let txs = slate_txs(wallet, slate.id)?;
+    // The other party may not have told its address, but it might be known
+    // from creating the transaction
+    let counterparty_address = counterparty_addresses(wallet, &txs)?.remove(&slate.id.to_string());
+    Ok(TxFinalizeResponse::new(txs, counterparty_address))
 }
 
-fn tx_send_address(
-    wallet: &Wallet,
+/// Send over Tor to the given Slatepack address, returns the slate id
+#[allow(clippy::too_many_arguments)]
+pub fn tx_send_address<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    config: &Config,
     address: &str,
     amount: u64,
     minimum_confirmations: u64,
     selection_strategy_is_use_all: bool,
-) -> Result<String, Error> {
+    fee_base: Option<u64>,
+) -> Result<TxSendAddressResponse, Error> {
+    let fee_base = fees::effective_fee_base(config, fee_base)?;
+    let address = SlatepackAddress::try_from(address)?;
     let parent_key_id = {
         wallet_lock!(wallet, w);
         w.parent_key_id().clone()
@@ -549,41 +995,51 @@ fn tx_send_address(
         let mut slate = tx::new_tx_slate(&mut **w, amount, false, 2, false, None)?;
         let height = w.w2n_client().get_chain_tip()?.0;
 
-        let context = tx::add_inputs_to_slate(
-            &mut **w,
-            None,
-            &mut slate,
-            height,
-            minimum_confirmations,
-            500,
-            1,
-            selection_strategy_is_use_all,
+        let mut context = fees::with_fee_base(fee_base, || {
+            tx::add_inputs_to_slate(
+                &mut **w,
+                keychain_mask,
+                &mut slate,
+                height,
+                minimum_confirmations,
+                config.max_outputs,
+                config.num_change_outputs,
+                selection_strategy_is_use_all,
+                &parent_key_id,
+                true,
+                false,
+                false,
+            )
+        })?;
+        // The recipient is known, so there is always a proof of the payment
+        let keychain = w.keychain(keychain_mask)?;
+        payment_proof::add_payment_proof(
+            &keychain,
             &parent_key_id,
-            true,
-            false,
-            false,
+            &mut slate,
+            &mut context,
+            &address,
         )?;
 
         {
-            let mut batch = w.batch(None)?;
+            let mut batch = w.batch(keychain_mask)?;
             batch.save_private_context(slate.id.as_bytes(), &context)?;
             batch.commit()?;
         }
 
-        selection::lock_tx_context(&mut **w, None, &slate, height, &context, None)?;
+        selection::lock_tx_context(&mut **w, keychain_mask, &slate, height, &context, None)?;
         slate.compact()?;
         slate
     };
 
     let api = Owner::new(wallet.clone(), None);
 
-    let address = SlatepackAddress::try_from(address)?;
     let tor_addr = OnionV3Address::try_from(&address)
         .map_err(|_| ErrorKind::GenericError(format!("{} is not SlatepackAddress", address)))?;
 
     let mut sender = HttpSlateSender::with_socks_proxy(
         &tor_addr.to_http_str(),
-        "127.0.0.1:39059",
+        &config.tor_socks_addr,
         "", // Ignored
         TorBridgeConfig::default(),
         TorProxyConfig::default(),
@@ -594,33 +1050,47 @@ fn tx_send_address(
 
     match sender.send_tx(&slate, false) {
         Ok(mut slate) => {
-            api.finalize_tx(None, &mut slate)?;
-            Ok(serde_json::to_string(&slate.id)
-                .map_err(|e| ErrorKind::GenericError(e.to_string()))?)
+            api.finalize_tx(keychain_mask, &mut slate)?;
+            save_counterparty(wallet, slate.id, &address)?;
+            Ok(TxSendAddressResponse::new(slate.id))
         }
         Err(e) => {
-            api.cancel_tx(None, None, Some(slate.id))?;
+            api.cancel_tx(keychain_mask, None, Some(slate.id))?;
             Err(Error::from(e))
         }
     }
 }
 
-fn tx_post(wallet: &Wallet, tx_slate_id: &str) -> Result<String, Error> {
+pub fn tx_post<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    tx_slate_id: &str,
+) -> Result<(), Error> {
     let api = Owner::new(wallet.clone(), None);
     let tx_uuid =
         Uuid::parse_str(tx_slate_id).map_err(|e| ErrorKind::GenericError(e.to_string()))?;
-    let (_, txs) = api.retrieve_txs(None, true, None, Some(tx_uuid.clone()), None)?;
-    if txs[0].confirmed {
+    // Refreshes the active account, the transaction may be in another one
+    // though, e.g. when it was a transfer between accounts
+    let (_, mut txs) = api.retrieve_txs(keychain_mask, true, None, Some(tx_uuid), None)?;
+    if txs.is_empty() {
+        txs = slate_txs(wallet, tx_uuid)?;
+    }
+    if txs.is_empty() {
+        return Err(
+            grin_wallet_libwallet::Error::TransactionDoesntExist(tx_slate_id.to_owned()).into(),
+        );
+    }
+    if txs.iter().any(|tx| tx.confirmed) {
         return Err(Error::from(ErrorKind::GenericError(format!(
             "Transaction with id {} is already confirmed. Not posting.",
             tx_slate_id
         ))));
     }
-    let stored_tx = api.get_stored_tx(None, None, Some(&tx_uuid))?;
+    let stored_tx = api.get_stored_tx(keychain_mask, None, Some(&tx_uuid))?;
     match stored_tx {
         Some(stored_tx) => {
-            api.post_tx(None, &stored_tx, true)?;
-            Ok("".to_owned())
+            api.post_tx(keychain_mask, &stored_tx, true)?;
+            Ok(())
         }
         None => Err(Error::from(ErrorKind::GenericError(format!(
             "Transaction with id {} does not have transaction data. Not posting.",
@@ -629,46 +1099,189 @@ fn tx_post(wallet: &Wallet, tx_slate_id: &str) -> Result<String, Error> {
     }
 }
 
-fn slatepack_decode(slatepack: &str) -> Result<String, Error> {
+pub fn slatepack_decode(slatepack: &str) -> Result<SlatepackDecodeResponse, Error> {
     let packer = Slatepacker::new(SlatepackerArgs {
         sender: None,
         recipients: vec![],
         dec_key: None,
     });
     let slatepack = packer.deser_slatepack(slatepack.as_bytes(), true)?;
+    if slatepack.mode == SLATEPACK_MODE_ENCRYPTED {
+        return Err(grin_wallet_libwallet::Error::SlatepackDecryption(
+            "Slatepack is encrypted, decode it with the recipient wallet".to_owned(),
+        )
+        .into());
+    }
     let slate = packer.get_slate(&slatepack)?;
-    Ok(serde_json::to_string(&VersionedSlate::into_version(
-        slate.clone(),
-        SlateVersion::V4,
+    Ok(SlatepackDecodeResponse::new(
+        VersionedSlate::into_version(slate, SlateVersion::V4)?,
+        slatepack.sender.map(|address| address.to_string()),
+    ))
+}
+
+/// Decode a slatepack with the key of the wallet, so slatepacks encrypted to
+/// its address can be read as well
+pub fn slatepack_decode_with_wallet<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    slatepack: &str,
+) -> Result<SlatepackDecodeResponse, Error> {
+    let (slate, sender) = decode_slatepack(wallet, keychain_mask, slatepack, None)?;
+    Ok(SlatepackDecodeResponse::new(
+        VersionedSlate::into_version(slate, SlateVersion::V4)?,
+        sender.map(|address| address.to_string()),
+    ))
+}
+
+/// Slate and sender address of a slatepack, decrypted with the key of the
+/// account it was encrypted to. The Owner API only tries the active account,
+/// so each account is made the active one in turn, `account` and the active
+/// account first.
+pub(crate) fn decode_slatepack<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    slatepack: &str,
+    account: Option<&str>,
+) -> Result<(Slate, Option<SlatepackAddress>), Error> {
+    wallet_lock!(wallet, w);
+    let active = w.parent_key_id();
+    let preferred = account.and_then(|label| {
+        w.acct_path_iter()
+            .find(|mapping| mapping.label == label)
+            .map(|mapping| mapping.path)
+    });
+    let mut paths: Vec<Identifier> = w.acct_path_iter().map(|mapping| mapping.path).collect();
+    paths.sort_by_key(|path| (Some(path) != preferred.as_ref(), *path != active));
+
+    let mut result = None;
+    for path in paths {
+        w.set_parent_key_id(path);
+        let decoded = owner::slate_from_slatepack_message(
+            &mut **w,
+            keychain_mask,
+            slatepack.to_owned(),
+            vec![0],
+        )
+        .and_then(|slate| {
+            let sender = owner::decode_slatepack_message(
+                &mut **w,
+                keychain_mask,
+                slatepack.to_owned(),
+                vec![0],
+            )?
+            .sender;
+            Ok((slate, sender))
+        });
+        match decoded {
+            Ok(decoded) => {
+                result = Some(Ok(decoded));
+                break;
+            }
+            // Report why the first account tried couldn't decode it
+            Err(e) => {
+                result.get_or_insert(Err(e));
+            }
+        }
+    }
+    // Never leave another account active than the caller had
+    w.set_parent_key_id(active);
+    match result {
+        Some(result) => Ok(result?),
+        None => Err(ErrorKind::GenericError("Wallet has no accounts".to_owned()).into()),
+    }
+}
+
+/// Tx log entries of a slate in all accounts of the wallet, the Owner API
+/// only looks at the active account
+pub(crate) fn slate_txs<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    slate_id: Uuid,
+) -> Result<Vec<TxLogEntry>, Error> {
+    wallet_lock!(wallet, w);
+    Ok(updater::retrieve_txs(
+        &mut **w,
+        None,
+        Some(slate_id),
+        None,
+        None,
+        false,
     )?)
-    .map_err(|e| ErrorKind::GenericError(e.to_string()))?)
 }
 
-fn get_grin_address(wallet: &Wallet) -> Result<String, Error> {
-    let keychain_mask = None;
+/// Armor a slate, encrypted to `recipient` if it's known. `sender` lets the
+/// recipient encrypt its reply in turn.
+pub(crate) fn encode_slatepack(
+    slate: &Slate,
+    sender: Option<SlatepackAddress>,
+    recipient: Option<SlatepackAddress>,
+) -> Result<String, Error> {
+    let packer = Slatepacker::new(SlatepackerArgs {
+        sender,
+        recipients: recipient.into_iter().collect(),
+        dec_key: None,
+    });
+    let slatepack = packer.create_slatepack(slate)?;
+    Ok(SlatepackArmor::encode(&slatepack).map_err(|e| ErrorKind::GenericError(e.to_string()))?)
+}
+
+pub fn get_grin_address<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+) -> Result<GrinAddressResponse, Error> {
     let mut w_lock = wallet.lock();
     let lc = w_lock.lc_provider()?;
     let w_inst = lc.wallet_inst()?;
-    let k = w_inst.keychain((keychain_mask).as_ref())?;
+    let k = w_inst.keychain(keychain_mask)?;
     let parent_key_id = w_inst.parent_key_id();
-    let sec_key = address::address_from_derivation_path(&k, &parent_key_id, 0)
+    let address = slatepack_address(&k, &parent_key_id)?;
+    Ok(GrinAddressResponse::new(address.to_string()))
+}
+
+/// Slatepack address of the account, the key behind it also signs payment
+/// proofs
+fn slatepack_address<K: Keychain>(
+    keychain: &K,
+    parent_key_id: &Identifier,
+) -> Result<SlatepackAddress, Error> {
+    let sec_key = address::address_from_derivation_path(keychain, parent_key_id, 0)
         .map_err(|e| ErrorKind::GenericError(e.to_string()))?;
     let onion_address = OnionV3Address::from_private(&sec_key.0)
         .map_err(|e| ErrorKind::GenericError(format!("{:?}", e).into()))?;
-    let address = SlatepackAddress::try_from(onion_address.clone())?;
-    Ok(address.to_string())
+    Ok(SlatepackAddress::try_from(onion_address)?)
+}
+
+/// Slatepack address of the account with `label`, or of the active account
+pub(crate) fn account_slatepack_address<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    label: Option<&str>,
+) -> Result<SlatepackAddress, Error> {
+    wallet_lock!(wallet, w);
+    let keychain = w.keychain(keychain_mask)?;
+    let path = label.and_then(|label| {
+        w.acct_path_iter()
+            .find(|mapping| mapping.label == label)
+            .map(|mapping| mapping.path)
+    });
+    let parent_key_id = match path {
+        Some(path) => path,
+        None => w.parent_key_id(),
+    };
+    slatepack_address(&keychain, &parent_key_id)
 }
 
-fn start_listen_with_http(
-    wallet: &Wallet,
+/// Start the Foreign API listener. It keeps a copy of `keychain_mask` for as
+/// long as it runs, since incoming transactions need the keychain.
+/// Start the foreign API for `wallet`, which must not be masked. The listener
+/// would need its own copy of the mask, which only the app is meant to hold.
+pub fn start_listen_with_http<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
     api_listen_addr: &str,
     apis: &mut ApiServer,
 ) -> Result<(), Error> {
-    let keychain_mask = None;
-
     let api_handler_v2 = grin_wallet_controller::ForeignAPIHandlerV2::new(
         wallet.clone(),
-        Arc::new(Mutex::new(keychain_mask)),
+        Arc::new(Mutex::new(None)),
         false,
         Mutex::new(None),
     );
@@ -682,9 +1295,12 @@ fn start_listen_with_http(
         "Starting HTTP Foreign listener API server at {}.",
         api_listen_addr
     );
-    let socket_addr: SocketAddr = api_listen_addr
-        .parse()
-        .expect("unable to parse socket address");
+    let socket_addr: SocketAddr = api_listen_addr.parse().map_err(|e| {
+        ErrorKind::GenericError(format!(
+            "Unable to parse socket address {}: {}",
+            api_listen_addr, e
+        ))
+    })?;
     let api_chan: &'static mut (oneshot::Sender<()>, oneshot::Receiver<()>) =
         Box::leak(Box::new(oneshot::channel::<()>()));
 
@@ -697,19 +1313,29 @@ fn start_listen_with_http(
     Ok(())
 }
 
-fn create_tor_config(wallet: &Wallet, listen_addr: &str) -> Result<String, Error> {
+pub fn create_tor_config<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    config: &Config,
+    listen_addr: &str,
+) -> Result<(), Error> {
     let mut w_lock = wallet.lock();
     let lc = w_lock.lc_provider()?;
     let w = lc.wallet_inst()?;
-    let keychain_mask = None;
-
-    let k = w.keychain((keychain_mask).as_ref())?;
+    let k = w.keychain(keychain_mask)?;
     let parent_key_id = w.parent_key_id();
     let tor_config_directory = format!("{}/tor", lc.get_top_level_directory()?);
 
     let sec_key = address::address_from_derivation_path(&k, &parent_key_id, 0)
         .map_err(|e| ErrorKind::GenericError(e.to_string()))?;
 
+    let socks_addr: SocketAddr = config.tor_socks_addr.parse().map_err(|e| {
+        ErrorKind::GenericError(format!(
+            "Unable to parse socket address {}: {}",
+            config.tor_socks_addr, e
+        ))
+    })?;
+
     let tor_data_dir = format!("{}{}{}", tor_config_directory, MAIN_SEPARATOR, "data");
 
     // create data directory if it doesn't exist
@@ -728,11 +1354,136 @@ fn create_tor_config(wallet: &Wallet, listen_addr: &str) -> Result<String, Error
     tor_config::output_torrc(
         &tor_config_directory,
         listen_addr,
-        "39059",
+        &socks_addr.port().to_string(),
         &service_dirs,
         hm_tor_bridge,
         hm_tor_proxy,
     )?;
 
-    Ok("".to_owned())
+    Ok(())
+}
+
+pub(crate) fn to_json<T: Serialize>(value: &T) -> Result<String, Error> {
+    Ok(serde_json::to_string(value).map_err(|e| ErrorKind::GenericError(e.to_string()))?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config_json(chain: &str) -> String {
+        format!(
+            r#"{{"wallet_dir": "/tmp/ironbelly", "check_node_api_http_addr": "http://127.0.0.1:3413", "chain": "{}", "account": null}}"#,
+            chain
+        )
+    }
+
+    #[test]
+    fn config_from_str() {
+        let config = Config::from_str(&config_json("mainnet")).unwrap();
+        assert_eq!(config.wallet_dir, "/tmp/ironbelly");
+        assert_eq!(config.account, None);
+        assert_eq!(config.accept_fee_base, 500000);
+        assert_eq!(config.api_listen_port, 3415);
+        assert_eq!(config.max_outputs, 500);
+        assert_eq!(config.num_change_outputs, 1);
+        assert_eq!(config.tor_socks_addr, "127.0.0.1:39059");
+        match Config::from_str("{}").unwrap_err().kind() {
+            ErrorKind::ConfigCannotBeParsed(_) => {}
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn config_validation() {
+        let with = |field: &str, value: serde_json::Value| {
+            let mut config: serde_json::Value =
+                serde_json::from_str(&config_json("mainnet")).unwrap();
+            config[field] = value;
+            Config::from_str(&config.to_string())
+        };
+        let config = with("num_change_outputs", 3.into()).unwrap();
+        assert_eq!(config.num_change_outputs, 3);
+        let wallet_config = create_wallet_config(with("accept_fee_base", 1000.into()).unwrap());
+        assert_eq!(wallet_config.unwrap().accept_fee_base, Some(1000));
+
+        for (field, value) in vec![
+            ("accept_fee_base", 0.into()),
+            ("api_listen_port", 0.into()),
+            ("api_listen_port", 70000.into()),
+            ("max_outputs", 0.into()),
+            ("num_change_outputs", 0.into()),
+            ("tor_socks_addr", "localhost".into()),
+            ("auto_lock_timeout", 0.into()),
+        ] {
+            match with(field, value).unwrap_err().kind() {
+                ErrorKind::ConfigCannotBeParsed(_) => {}
+                kind => panic!("unexpected error kind {:?} for {}", kind, field),
+            }
+        }
+    }
+
+    #[test]
+    fn wallet_config_chain_type() {
+        for (chain, chain_type) in vec![
+            ("mainnet", ChainTypes::Mainnet),
+            ("floonet", ChainTypes::Testnet),
+            ("usertesting", ChainTypes::UserTesting),
+            ("automatedtesting", ChainTypes::AutomatedTesting),
+            ("unknown", ChainTypes::Testnet),
+        ] {
+            let config = Config::from_str(&config_json(chain)).unwrap();
+            let wallet_config = create_wallet_config(config).unwrap();
+            assert_eq!(wallet_config.chain_type, Some(chain_type));
+            assert_eq!(wallet_config.data_file_dir, "/tmp/ironbelly");
+        }
+    }
+
+    #[test]
+    fn config_wallet_name() {
+        let config = Config::from_str(&config_json("mainnet")).unwrap();
+        assert_eq!(config.wallet_name(), DEFAULT_WALLET_NAME);
+        assert_eq!(config.data_dir(), "/tmp/ironbelly");
+
+        let mut json: serde_json::Value = serde_json::from_str(&config_json("mainnet")).unwrap();
+        json["wallet_name"] = "business".into();
+        let config = Config::from_str(&json.to_string()).unwrap();
+        assert_eq!(config.data_dir(), "/tmp/ironbelly/wallets/business");
+        let wallet_config = create_wallet_config(config).unwrap();
+        assert_eq!(
+            wallet_config.data_file_dir,
+            "/tmp/ironbelly/wallets/business"
+        );
+
+        let too_long = "x".repeat(65);
+        for name in vec!["", "..", "a/b", "a b", too_long.as_str()] {
+            json["wallet_name"] = name.into();
+            match Config::from_str(&json.to_string()).unwrap_err().kind() {
+                ErrorKind::InvalidWalletName(_) => {}
+                kind => panic!("unexpected error kind {:?} for {:?}", kind, name),
+            }
+        }
+    }
+
+    #[test]
+    fn seed_new_word_count() {
+        assert_eq!(seed_new(16).unwrap().phrase.split_whitespace().count(), 12);
+        assert_eq!(seed_new(32).unwrap().phrase.split_whitespace().count(), 24);
+    }
+
+    #[test]
+    fn responses_are_versioned() {
+        let json = to_json(&WalletPmmrRangeResponse::new(1, 10)).unwrap();
+        let value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        assert_eq!(value["version"], RESPONSE_VERSION);
+        assert_eq!(value["lowest_index"], 1);
+        assert_eq!(value["highest_index"], 10);
+        let json = to_json(&EmptyResponse::new()).unwrap();
+        assert_eq!(json, format!(r#"{{"version":{}}}"#, RESPONSE_VERSION));
+    }
+
+    #[test]
+    fn slatepack_decode_invalid() {
+        assert!(slatepack_decode("BEGINSLATEPACK. garbage. ENDSLATEPACK.").is_err());
+    }
 }
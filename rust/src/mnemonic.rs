@@ -0,0 +1,197 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// Recovery phrase checks done before a wallet is restored, so the app gets
+// precise errors instead of a failure deep inside the lifecycle provider.
+use crate::errors::{Error, ErrorKind};
+use crate::responses::MnemonicValidateResponse;
+use grin_keychain::mnemonic::{self, WORDS};
+use serde::{Deserialize, Serialize};
+
+/// Word counts of the phrases `WalletSeed` can be restored from
+const VALID_WORD_COUNTS: [usize; 5] = [12, 15, 18, 21, 24];
+/// Suggestions further away than this are more confusing than helpful
+const MAX_SUGGESTION_DISTANCE: usize = 2;
+const MAX_SUGGESTIONS: usize = 3;
+
+/// Word of a phrase which is not in the BIP-39 word list
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "schema", derive(schemars::JsonSchema))]
+pub struct InvalidWord {
+    /// Position of the word in the phrase, starting at 0
+    pub index: usize,
+    pub word: String,
+    /// Closest words of the word list, best match first
+    pub suggestions: Vec<String>,
+}
+
+pub fn mnemonic_validate(phrase: &str) -> Result<MnemonicValidateResponse, Error> {
+    let words = normalize(phrase);
+    let invalid_words: Vec<InvalidWord> = words
+        .iter()
+        .enumerate()
+        .filter(|(_, word)| mnemonic::search(word).is_err())
+        .map(|(index, word)| InvalidWord {
+            index,
+            word: word.clone(),
+            suggestions: suggestions(word),
+        })
+        .collect();
+    let length_valid = VALID_WORD_COUNTS.contains(&words.len());
+    // The checksum can only be computed from known words of a valid length
+    let checksum_valid =
+        invalid_words.is_empty() && length_valid && mnemonic::to_entropy(&words.join(" ")).is_ok();
+    Ok(MnemonicValidateResponse::new(
+        checksum_valid,
+        words.len(),
+        length_valid,
+        checksum_valid,
+        invalid_words,
+    ))
+}
+
+/// Fail with a message describing what is wrong with the phrase, returns the
+/// phrase in lowercase with single spaces between the words otherwise
+pub(crate) fn check_mnemonic(phrase: &str) -> Result<String, Error> {
+    let result = mnemonic_validate(phrase)?;
+    let message = if !result.invalid_words.is_empty() {
+        let words: Vec<String> = result
+            .invalid_words
+            .iter()
+            .map(|w| match w.suggestions.first() {
+                Some(suggestion) => format!("{} (did you mean {}?)", w.word, suggestion),
+                None => w.word.clone(),
+            })
+            .collect();
+        format!("unknown words {}", words.join(", "))
+    } else if !result.length_valid {
+        format!("{} words, expected 12, 15, 18, 21 or 24", result.word_count)
+    } else if !result.checksum_valid {
+        "checksum doesn't match, some words are wrong or in the wrong order".to_owned()
+    } else {
+        return Ok(normalize(phrase).join(" "));
+    };
+    Err(ErrorKind::InvalidMnemonic(message).into())
+}
+
+fn normalize(phrase: &str) -> Vec<String> {
+    phrase
+        .split_whitespace()
+        .map(|word| word.to_lowercase())
+        .collect()
+}
+
+fn suggestions(word: &str) -> Vec<String> {
+    let mut candidates: Vec<(usize, &String)> = WORDS
+        .iter()
+        .filter_map(|candidate| {
+            // A typed prefix, e.g. "aban", is as good as an exact match
+            let distance = if candidate.starts_with(word) {
+                0
+            } else {
+                levenshtein(word, candidate)
+            };
+            if distance <= MAX_SUGGESTION_DISTANCE {
+                Some((distance, candidate))
+            } else {
+                None
+            }
+        })
+        .collect();
+    candidates.sort();
+    candidates
+        .into_iter()
+        .take(MAX_SUGGESTIONS)
+        .map(|(_, candidate)| candidate.clone())
+        .collect()
+}
+
+fn levenshtein(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, ca) in a.chars().enumerate() {
+        let mut previous = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let current = row[j + 1];
+            row[j + 1] = if ca == *cb {
+                previous
+            } else {
+                1 + previous.min(row[j]).min(current)
+            };
+            previous = current;
+        }
+    }
+    row[b.len()]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const VALID_PHRASE: &str = "abandon abandon abandon abandon abandon abandon \
+                                abandon abandon abandon abandon abandon about";
+
+    #[test]
+    fn valid_phrase() {
+        let result = mnemonic_validate(VALID_PHRASE).unwrap();
+        assert!(result.valid);
+        assert_eq!(result.word_count, 12);
+        assert!(result.invalid_words.is_empty());
+        let phrase = format!("  {} ", VALID_PHRASE.to_uppercase().replace(' ', " \n "));
+        assert_eq!(check_mnemonic(&phrase).unwrap(), VALID_PHRASE);
+        let phrase = crate::seed_new(32).unwrap().phrase;
+        assert!(mnemonic_validate(&phrase).unwrap().valid);
+    }
+
+    #[test]
+    fn invalid_words_get_suggestions() {
+        let phrase = VALID_PHRASE.replacen("abandon", "abandn", 1) + " aboutt";
+        let result = mnemonic_validate(&phrase).unwrap();
+        assert!(!result.valid);
+        assert!(!result.length_valid);
+        assert_eq!(result.invalid_words.len(), 2);
+        assert_eq!(result.invalid_words[0].index, 0);
+        assert_eq!(result.invalid_words[0].suggestions[0], "abandon");
+        assert_eq!(result.invalid_words[1].index, 12);
+        assert_eq!(result.invalid_words[1].suggestions[0], "about");
+        let unknown = mnemonic_validate("qqqqqqqq").unwrap();
+        assert!(unknown.invalid_words[0].suggestions.is_empty());
+    }
+
+    #[test]
+    fn wrong_length_and_checksum() {
+        let result = mnemonic_validate("abandon abandon abandon").unwrap();
+        assert!(!result.length_valid);
+        assert!(!result.checksum_valid);
+
+        let result = mnemonic_validate(&"abandon ".repeat(12)).unwrap();
+        assert!(result.length_valid);
+        assert!(result.invalid_words.is_empty());
+        assert!(!result.checksum_valid);
+        match check_mnemonic(&"abandon ".repeat(12)).unwrap_err().kind() {
+            ErrorKind::InvalidMnemonic(_) => {}
+            kind => panic!("unexpected error kind {:?}", kind),
+        }
+    }
+
+    #[test]
+    fn levenshtein_distance() {
+        assert_eq!(levenshtein("abandon", "abandon"), 0);
+        assert_eq!(levenshtein("abandn", "abandon"), 1);
+        assert_eq!(levenshtein("kitten", "sitting"), 3);
+        assert_eq!(levenshtein("", "able"), 4);
+    }
+}
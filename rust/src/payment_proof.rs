@@ -0,0 +1,80 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// Payment proofs let a sender show a third party that an amount was paid to a
+// Slatepack address. The sender puts both addresses into the slate, the
+// recipient signs the amount and kernel excess with the key of its address
+// while receiving, and the signed proof is stored with the sender's tx log
+// entry once the transaction is finalized.
+use crate::errors::Error;
+use crate::responses::{PaymentProofExportResponse, PaymentProofVerifyResponse};
+use crate::{slatepack_address, SecretKey, WalletInstance};
+use grin_keychain::{ExtKeychain, Identifier};
+use grin_wallet_api::Owner;
+use grin_wallet_libwallet::{
+    Context, NodeClient, PaymentInfo, PaymentProof, Slate, SlatepackAddress,
+};
+use uuid::Uuid;
+
+/// Ask the recipient at `recipient_address` for a proof of the payment. Has
+/// to be called before the context is saved and the outputs are locked, so
+/// the proof ends up in the tx log entry.
+pub(crate) fn add_payment_proof(
+    keychain: &ExtKeychain,
+    parent_key_id: &Identifier,
+    slate: &mut Slate,
+    context: &mut Context,
+    recipient_address: &SlatepackAddress,
+) -> Result<(), Error> {
+    let sender_address = slatepack_address(keychain, parent_key_id)?;
+    slate.payment_proof = Some(PaymentInfo {
+        sender_address: sender_address.pub_key,
+        receiver_address: recipient_address.pub_key,
+        receiver_signature: None,
+    });
+    // Same derivation index as the Slatepack address of the account
+    context.payment_proof_derivation_index = Some(0);
+    Ok(())
+}
+
+/// Proof of a sent and confirmed transaction, to be handed to whoever wants
+/// to check the payment
+pub fn payment_proof_export<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    tx_slate_id: &str,
+) -> Result<PaymentProofExportResponse, Error> {
+    let tx_slate_id = Uuid::parse_str(tx_slate_id).map_err(|e| {
+        grin_wallet_libwallet::Error::ArgumentError(format!("Invalid slate id: {}", e))
+    })?;
+    let api = Owner::new(wallet.clone(), None);
+    let proof = api.retrieve_payment_proof(keychain_mask, true, None, Some(tx_slate_id))?;
+    Ok(PaymentProofExportResponse::new(proof))
+}
+
+/// Check a proof in its JSON form, fails if the signatures don't match or the
+/// kernel is not on chain. The response tells whether this wallet is one of
+/// the parties.
+pub fn payment_proof_verify<C: NodeClient + 'static>(
+    wallet: &WalletInstance<C>,
+    keychain_mask: Option<&SecretKey>,
+    proof_json: &str,
+) -> Result<PaymentProofVerifyResponse, Error> {
+    let proof: PaymentProof = serde_json::from_str(proof_json)
+        .map_err(|e| grin_wallet_libwallet::Error::PaymentProofParsing(e.to_string()))?;
+    let api = Owner::new(wallet.clone(), None);
+    let (is_sender, is_recipient) = api.verify_payment_proof(keychain_mask, &proof)?;
+    Ok(PaymentProofVerifyResponse::new(is_sender, is_recipient))
+}
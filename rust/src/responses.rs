@@ -0,0 +1,327 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// Results of every bridge call. Each response carries `version`, which is
+// bumped on any incompatible change of these structs, so the app can detect
+// that it talks to a wallet core it doesn't understand.
+//
+// JSON Schema and TypeScript definitions are generated from these structs with
+// `cargo run --features schema --bin ironbelly-schema`.
+use crate::accounts::Account;
+use crate::handles::Handle;
+use crate::mnemonic::InvalidWord;
+use crate::Strategy;
+use grin_wallet_libwallet::{PaymentProof, TxLogEntry, VersionedSlate, WalletInfo};
+#[cfg(feature = "schema")]
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use uuid::Uuid;
+
+/// Version of the response structs below
+pub const RESPONSE_VERSION: u32 = 1;
+
+macro_rules! response (
+    ($(#[$meta:meta])* $name:ident { $($(#[$field_meta:meta])* $field:ident: $ty:ty),* $(,)? }) => (
+        $(#[$meta])*
+        #[derive(Serialize, Deserialize)]
+        #[cfg_attr(feature = "schema", derive(JsonSchema))]
+        pub struct $name {
+            pub version: u32,
+            $($(#[$field_meta])* pub $field: $ty,)*
+        }
+
+        impl $name {
+            #[allow(clippy::new_without_default)]
+            pub fn new($($field: $ty),*) -> Self {
+                $name {
+                    version: RESPONSE_VERSION,
+                    $($field,)*
+                }
+            }
+        }
+    )
+);
+
+response!(
+    /// Result of calls which don't return anything
+    EmptyResponse {}
+);
+
+response!(
+    /// Opened wallet
+    OpenWalletResponse {
+        wallet: Handle,
+        /// Hex encoded keychain mask, only set when opened with `use_mask`
+        keychain_mask: Option<String>,
+    }
+);
+
+response!(
+    /// Payment proof of a sent transaction
+    PaymentProofExportResponse {
+        #[cfg_attr(feature = "schema", schemars(with = "schema::PaymentProof"))]
+        proof: PaymentProof,
+    }
+);
+
+response!(
+    /// Valid payment proof and the parties of it this wallet is
+    PaymentProofVerifyResponse {
+        is_sender: bool,
+        is_recipient: bool,
+    }
+);
+
+response!(
+    /// Recovery phrase of a new or existing wallet
+    PhraseResponse { phrase: String }
+);
+
+response!(
+    /// Result of checking a recovery phrase, `valid` only if every check passed
+    MnemonicValidateResponse {
+        valid: bool,
+        word_count: usize,
+        length_valid: bool,
+        checksum_valid: bool,
+        invalid_words: Vec<InvalidWord>,
+    }
+);
+
+response!(
+    /// Names of the wallets in the wallet directory
+    WalletsListResponse { wallets: Vec<String> }
+);
+
+response!(
+    /// Progress of a UTXO set scan
+    WalletScanOutputsResponse { last_retrieved_index: u64 }
+);
+
+response!(
+    /// PMMR indices to scan
+    WalletPmmrRangeResponse {
+        lowest_index: u64,
+        highest_index: u64,
+    }
+);
+
+response!(
+    /// Single transaction, `txs` is empty if it doesn't exist
+    TxGetResponse {
+        refreshed: bool,
+        #[cfg_attr(feature = "schema", schemars(with = "Vec<schema::TxLogEntry>"))]
+        txs: Vec<TxLogEntry>,
+        /// Slatepack address of the other party by slate id, if known
+        counterparty_addresses: BTreeMap<String, String>,
+    }
+);
+
+response!(
+    /// Transaction log and balance of the wallet
+    TxsGetResponse {
+        refreshed: bool,
+        #[cfg_attr(feature = "schema", schemars(with = "Vec<schema::TxLogEntry>"))]
+        txs: Vec<TxLogEntry>,
+        #[cfg_attr(feature = "schema", schemars(with = "schema::WalletInfo"))]
+        wallet_info: WalletInfo,
+        /// Slatepack address of the other party by slate id, if known
+        counterparty_addresses: BTreeMap<String, String>,
+    }
+);
+
+response!(
+    /// Output selection strategies available for an amount
+    TxStrategiesResponse { strategies: Vec<Strategy> }
+);
+
+response!(
+    /// Fee of a transaction and what it would be made of
+    TxFeeEstimateResponse {
+        fee: u64,
+        /// Fee per weight unit the fee is computed with
+        fee_base: u64,
+        /// Fee weight of the transaction, `fee` is `weight * fee_base`
+        weight: u64,
+        /// Number of outputs spent
+        inputs: usize,
+        /// Number of outputs created, the recipient's one included
+        outputs: usize,
+        /// Sum of the spent outputs
+        total: u64,
+    }
+);
+
+response!(
+    /// Created transaction and the slatepack to hand to the recipient
+    TxCreateResponse {
+        #[cfg_attr(feature = "schema", schemars(with = "Vec<schema::TxLogEntry>"))]
+        txs: Vec<TxLogEntry>,
+        slatepack: String,
+    }
+);
+
+response!(
+    /// Both tx log entries of a transfer between accounts
+    TxTransferResponse {
+        #[cfg_attr(feature = "schema", schemars(with = "Vec<schema::TxLogEntry>"))]
+        txs: Vec<TxLogEntry>,
+    }
+);
+
+response!(
+    /// Received transaction and the slatepack to return to the sender
+    TxReceiveResponse {
+        #[cfg_attr(feature = "schema", schemars(with = "Vec<schema::TxLogEntry>"))]
+        txs: Vec<TxLogEntry>,
+        slatepack: String,
+        /// Slatepack address of the sender, if it told it
+        counterparty_address: Option<String>,
+    }
+);
+
+response!(
+    /// Finalized transaction
+    TxFinalizeResponse {
+        #[cfg_attr(feature = "schema", schemars(with = "Vec<schema::TxLogEntry>"))]
+        txs: Vec<TxLogEntry>,
+        /// Slatepack address of the recipient, if known
+        counterparty_address: Option<String>,
+    }
+);
+
+response!(
+    /// Issued invoice and the slatepack to hand to the payer
+    TxIssueInvoiceResponse {
+        #[cfg_attr(feature = "schema", schemars(with = "Vec<schema::TxLogEntry>"))]
+        txs: Vec<TxLogEntry>,
+        slatepack: String,
+    }
+);
+
+response!(
+    /// Paid invoice and the slatepack to return to the issuer
+    TxProcessInvoiceResponse {
+        #[cfg_attr(feature = "schema", schemars(with = "Vec<schema::TxLogEntry>"))]
+        txs: Vec<TxLogEntry>,
+        slatepack: String,
+    }
+);
+
+response!(
+    /// Transaction sent to a Slatepack address
+    TxSendAddressResponse {
+        #[cfg_attr(feature = "schema", schemars(with = "String"))]
+        slate_id: Uuid,
+    }
+);
+
+response!(
+    /// Slate contained in a slatepack
+    SlatepackDecodeResponse {
+        #[cfg_attr(feature = "schema", schemars(with = "serde_json::Value"))]
+        slate: VersionedSlate,
+        /// Slatepack address of whoever created the slatepack, if it is in
+        /// there
+        sender_address: Option<String>,
+    }
+);
+
+response!(
+    /// Slatepack address of the wallet
+    GrinAddressResponse { address: String }
+);
+
+response!(
+    /// Started HTTP listener
+    StartListenResponse { api_server: Handle }
+);
+
+response!(
+    /// Accounts of the wallet
+    AccountsListResponse { accounts: Vec<Account> }
+);
+
+response!(
+    /// Created, renamed or activated account
+    AccountResponse { account: Account }
+);
+
+/// Schema-only mirrors of the grin types embedded in responses, matching
+/// their serde representation
+#[cfg(feature = "schema")]
+pub mod schema {
+    use schemars::JsonSchema;
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    pub struct PaymentProof {
+        pub amount: String,
+        pub excess: String,
+        pub recipient_address: String,
+        pub recipient_sig: String,
+        pub sender_address: String,
+        pub sender_sig: String,
+    }
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    pub struct TxLogEntry {
+        pub parent_key_id: String,
+        pub id: u32,
+        pub tx_slate_id: Option<String>,
+        pub tx_type: TxLogEntryType,
+        pub creation_ts: String,
+        pub confirmation_ts: Option<String>,
+        pub confirmed: bool,
+        pub num_inputs: usize,
+        pub num_outputs: usize,
+        pub amount_credited: String,
+        pub amount_debited: String,
+        pub fee: Option<String>,
+        pub ttl_cutoff_height: Option<String>,
+        pub stored_tx: Option<String>,
+        pub kernel_excess: Option<String>,
+        pub kernel_lookup_min_height: Option<String>,
+        pub payment_proof: Option<serde_json::Value>,
+        pub reverted_after: Option<serde_json::Value>,
+    }
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    pub enum TxLogEntryType {
+        ConfirmedCoinbase,
+        TxReceived,
+        TxSent,
+        TxReceivedCancelled,
+        TxSentCancelled,
+        TxReverted,
+    }
+
+    #[allow(dead_code)]
+    #[derive(JsonSchema)]
+    pub struct WalletInfo {
+        pub last_confirmed_height: String,
+        pub minimum_confirmations: String,
+        pub total: String,
+        pub amount_awaiting_finalization: String,
+        pub amount_awaiting_confirmation: String,
+        pub amount_immature: String,
+        pub amount_currently_spendable: String,
+        pub amount_locked: String,
+        pub amount_reverted: String,
+    }
+}
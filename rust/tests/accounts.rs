@@ -0,0 +1,243 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#[allow(dead_code)]
+mod common;
+
+use common::TestNode;
+use grin_core::consensus::GRIN_BASE;
+use grin_wallet_libwallet::TxLogEntryType;
+use wallet::ErrorKind;
+
+const MIN_CONF: u64 = 1;
+
+#[test]
+fn create_and_rename_accounts() {
+    let mut node = TestNode::new("create_and_rename_accounts");
+    let wallet = node.add_wallet("wallet");
+    node.start();
+    node.mine(&wallet, 3);
+    wallet::txs_get(&wallet, None, MIN_CONF, true).unwrap();
+
+    let accounts = wallet::accounts_list(&wallet, None, MIN_CONF)
+        .unwrap()
+        .accounts;
+    assert_eq!(accounts.len(), 1);
+    assert_eq!(accounts[0].label, "default");
+    assert_eq!(accounts[0].path, "m/0/0");
+    assert!(accounts[0].info.total > 0);
+
+    let created = wallet::account_create(&wallet, None, "savings")
+        .unwrap()
+        .account;
+    assert_eq!(created.path, "m/1/0");
+    assert_eq!(created.info.total, 0);
+    let error = wallet::account_create(&wallet, None, "savings")
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(error.kind().code(), "account_already_exists");
+    assert!(wallet::account_create(&wallet, None, " ").is_err());
+
+    let renamed = wallet::account_rename(&wallet, None, "savings", "project")
+        .unwrap()
+        .account;
+    assert_eq!(renamed.path, "m/1/0");
+    let error = wallet::account_rename(&wallet, None, "savings", "other")
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(error.kind().code(), "unknown_account");
+    assert!(wallet::account_rename(&wallet, None, "default", "main").is_err());
+
+    let labels: Vec<String> = wallet::accounts_list(&wallet, None, MIN_CONF)
+        .unwrap()
+        .accounts
+        .into_iter()
+        .map(|account| account.label)
+        .collect();
+    assert_eq!(labels, vec!["default", "project"]);
+}
+
+#[test]
+fn switch_active_account() {
+    let mut node = TestNode::new("switch_active_account");
+    let wallet = node.add_wallet("wallet");
+    node.start();
+    node.mine(&wallet, 3);
+    let config = node.wallet_config("wallet");
+    let default_address = wallet::get_grin_address(&wallet, None).unwrap().address;
+    wallet::account_create(&wallet, None, "savings").unwrap();
+
+    let active = wallet::wallet_set_active_account(&wallet, "savings")
+        .unwrap()
+        .account;
+    assert_eq!(active.path, "m/1/0");
+    let savings_address = wallet::get_grin_address(&wallet, None).unwrap().address;
+    assert_ne!(savings_address, default_address);
+    let info = wallet::txs_get(&wallet, None, MIN_CONF, true)
+        .unwrap()
+        .wallet_info;
+    assert_eq!(info.total, 0);
+    let error = wallet::tx_create(
+        &wallet, None, &config, 1_000_000, MIN_CONF, false, None, None,
+    )
+    .map(|_| ())
+    .unwrap_err();
+    assert_eq!(error.kind().code(), "not_enough_funds");
+
+    wallet::wallet_set_active_account(&wallet, "default").unwrap();
+    assert_eq!(
+        wallet::get_grin_address(&wallet, None).unwrap().address,
+        default_address
+    );
+    assert!(
+        wallet::txs_get(&wallet, None, MIN_CONF, true)
+            .unwrap()
+            .wallet_info
+            .total
+            > 0
+    );
+    wallet::tx_create(
+        &wallet, None, &config, 1_000_000, MIN_CONF, false, None, None,
+    )
+    .unwrap();
+
+    assert_eq!(
+        wallet::wallet_set_active_account(&wallet, "missing")
+            .map(|_| ())
+            .unwrap_err()
+            .kind(),
+        ErrorKind::AccountNotFound("missing".to_owned())
+    );
+}
+
+#[test]
+fn transfer_between_accounts() {
+    let mut node = TestNode::new("transfer_between_accounts");
+    let wallet = node.add_wallet("wallet");
+    node.start();
+    node.mine(&wallet, 10);
+    let config = node.wallet_config("wallet");
+    wallet::txs_get(&wallet, None, MIN_CONF, true).unwrap();
+    wallet::account_create(&wallet, None, "savings").unwrap();
+
+    let amount = 2 * GRIN_BASE;
+    let txs = wallet::tx_transfer_between_accounts(
+        &wallet, None, &config, "default", "savings", amount, MIN_CONF, false,
+    )
+    .unwrap()
+    .txs;
+    assert_eq!(txs.len(), 2);
+    assert_eq!(txs[0].tx_slate_id, txs[1].tx_slate_id);
+    let types: Vec<TxLogEntryType> = txs.iter().map(|tx| tx.tx_type.clone()).collect();
+    assert!(types.contains(&TxLogEntryType::TxSent));
+    assert!(types.contains(&TxLogEntryType::TxReceived));
+
+    wallet::wallet_set_active_account(&wallet, "savings").unwrap();
+    let resp = wallet::txs_get(&wallet, None, MIN_CONF, true).unwrap();
+    assert_eq!(resp.txs.len(), 1);
+    assert_eq!(resp.wallet_info.amount_currently_spendable, amount);
+
+    // Between two accounts which are both not the active one
+    wallet::account_create(&wallet, None, "spare").unwrap();
+    wallet::wallet_set_active_account(&wallet, "spare").unwrap();
+    let txs = wallet::tx_transfer_between_accounts(
+        &wallet, None, &config, "savings", "default", GRIN_BASE, MIN_CONF, false,
+    )
+    .unwrap()
+    .txs;
+    assert_eq!(txs.len(), 2);
+    let slate_id = txs[0].tx_slate_id.unwrap().to_string();
+    wallet::wallet_set_active_account(&wallet, "savings").unwrap();
+    let resp = wallet::txs_get(&wallet, None, MIN_CONF, true).unwrap();
+    assert_eq!(resp.txs.len(), 2);
+    assert!(resp.wallet_info.amount_currently_spendable < amount - GRIN_BASE);
+    // `tx_post` finds the transaction in the other accounts to retry a post
+    wallet::wallet_set_active_account(&wallet, "spare").unwrap();
+    match wallet::tx_post(&wallet, None, &slate_id)
+        .unwrap_err()
+        .kind()
+    {
+        ErrorKind::GenericError(message) => assert!(message.contains("already confirmed")),
+        kind => panic!("unexpected error kind {:?}", kind),
+    }
+    wallet::wallet_set_active_account(&wallet, "savings").unwrap();
+
+    let error = wallet::tx_transfer_between_accounts(
+        &wallet, None, &config, "savings", "savings", amount, MIN_CONF, false,
+    )
+    .map(|_| ())
+    .unwrap_err();
+    assert_eq!(error.kind().code(), "argument");
+    assert_eq!(
+        wallet::tx_transfer_between_accounts(
+            &wallet, None, &config, "savings", "missing", amount, MIN_CONF, false,
+        )
+        .map(|_| ())
+        .unwrap_err()
+        .kind(),
+        ErrorKind::AccountNotFound("missing".to_owned())
+    );
+}
+
+#[test]
+fn failed_transfer_is_cancelled_in_both_accounts() {
+    let mut node = TestNode::new("failed_transfer_is_cancelled_in_both_accounts");
+    let wallet = node.add_wallet("wallet");
+    node.start();
+    node.mine(&wallet, 10);
+    let config = node.wallet_config("wallet");
+    let info = wallet::txs_get(&wallet, None, MIN_CONF, true)
+        .unwrap()
+        .wallet_info;
+    wallet::account_create(&wallet, None, "savings").unwrap();
+    wallet::account_create(&wallet, None, "spare").unwrap();
+
+    // Neither of the accounts is the active one
+    wallet::wallet_set_active_account(&wallet, "spare").unwrap();
+    let error = wallet::accounts::tx_transfer_between_accounts_with_finalize(
+        &wallet,
+        None,
+        &config,
+        "default",
+        "savings",
+        2 * GRIN_BASE,
+        MIN_CONF,
+        false,
+        |_| Err(ErrorKind::GenericError("finalize failed".to_owned()).into()),
+    )
+    .map(|_| ())
+    .unwrap_err();
+    assert_eq!(
+        error.kind(),
+        ErrorKind::GenericError("finalize failed".to_owned())
+    );
+
+    wallet::wallet_set_active_account(&wallet, "default").unwrap();
+    let resp = wallet::txs_get(&wallet, None, MIN_CONF, false).unwrap();
+    assert_eq!(resp.wallet_info.amount_locked, 0);
+    assert_eq!(
+        resp.wallet_info.amount_currently_spendable,
+        info.amount_currently_spendable
+    );
+    assert_eq!(
+        resp.txs.last().unwrap().tx_type,
+        TxLogEntryType::TxSentCancelled
+    );
+
+    wallet::wallet_set_active_account(&wallet, "savings").unwrap();
+    let txs = wallet::txs_get(&wallet, None, MIN_CONF, false).unwrap().txs;
+    assert_eq!(txs.len(), 1);
+    assert_eq!(txs[0].tx_type, TxLogEntryType::TxReceivedCancelled);
+}
@@ -0,0 +1,127 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+// Test harness running wallets against an in-process mock node
+// (`grin_wallet_impls::test_framework::WalletProxy`) on the
+// `AutomatedTesting` chain, so no network access is needed.
+use grin_chain::Chain;
+use grin_core::global::{self, ChainTypes};
+use grin_keychain::ExtKeychain;
+use grin_wallet_impls::test_framework::{self, LocalWalletClient, WalletProxy};
+use grin_wallet_impls::DefaultLCProvider;
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use wallet::{Config, WalletInstance};
+
+pub type TestWallet = WalletInstance<LocalWalletClient>;
+
+type Proxy = WalletProxy<
+    'static,
+    DefaultLCProvider<'static, LocalWalletClient, ExtKeychain>,
+    LocalWalletClient,
+    ExtKeychain,
+>;
+
+pub const PASSWORD: &str = "password";
+
+/// Mock node with a set of wallets attached to it
+pub struct TestNode {
+    pub test_dir: String,
+    proxy: Option<Proxy>,
+    running: Arc<AtomicBool>,
+    chain: Arc<Chain>,
+}
+
+impl TestNode {
+    pub fn new(name: &str) -> Self {
+        let test_dir = format!("target/test_output/{}", name);
+        let _ = fs::remove_dir_all(&test_dir);
+        global::set_local_chain_type(ChainTypes::AutomatedTesting);
+        let proxy = Proxy::new(&test_dir);
+        TestNode {
+            test_dir,
+            running: proxy.running.clone(),
+            chain: proxy.chain.clone(),
+            proxy: Some(proxy),
+        }
+    }
+
+    pub fn config(&self, name: &str) -> String {
+        serde_json::json!({
+            "wallet_dir": format!("{}/{}", self.test_dir, name),
+            "check_node_api_http_addr": "http://127.0.0.1:3413",
+            "chain": "automatedtesting",
+            "account": "default",
+        })
+        .to_string()
+    }
+
+    /// Config of the wallet `wallet_name` in a directory shared by all such
+    /// wallets of this node
+    pub fn shared_config(&self, wallet_name: &str) -> String {
+        let mut config: serde_json::Value = serde_json::from_str(&self.config("shared")).unwrap();
+        config["wallet_name"] = wallet_name.into();
+        config.to_string()
+    }
+
+    pub fn wallet_config(&self, name: &str) -> Config {
+        Config::from_str(&self.config(name)).unwrap()
+    }
+
+    /// Create a wallet with a fresh seed, open it and attach it to the node.
+    /// Must be called before `start`.
+    pub fn add_wallet(&mut self, name: &str) -> TestWallet {
+        let config = self.config(name);
+        self.add_wallet_with_config(name, &config)
+    }
+
+    /// Same as `add_wallet`, but the wallet is created with `config`
+    pub fn add_wallet_with_config(&mut self, name: &str, config: &str) -> TestWallet {
+        let proxy = self.proxy.as_mut().expect("node is already started");
+        let client = LocalWalletClient::new(name, proxy.tx.clone());
+        let phrase = wallet::seed_new(32).unwrap().phrase;
+        wallet::wallet_init_with_client(config, &phrase, PASSWORD, client.clone()).unwrap();
+        let wallet = wallet::open_wallet_with_client(config, PASSWORD, client.clone()).unwrap();
+        proxy.add_wallet(name, client.get_send_instance(), wallet.clone(), None);
+        wallet
+    }
+
+    pub fn start(&mut self) {
+        let mut proxy = self.proxy.take().expect("node is already started");
+        thread::spawn(move || {
+            global::set_local_chain_type(ChainTypes::AutomatedTesting);
+            if let Err(e) = proxy.run() {
+                panic!("Wallet proxy error: {}", e);
+            }
+        });
+    }
+
+    /// Mine `number` blocks with coinbase outputs going to `wallet`
+    pub fn mine(&self, wallet: &TestWallet, number: usize) {
+        test_framework::award_blocks_to_wallet(&self.chain, wallet.clone(), None, number, false)
+            .unwrap();
+    }
+}
+
+impl Drop for TestNode {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::Relaxed);
+        thread::sleep(Duration::from_millis(200));
+        let _ = fs::remove_dir_all(&self.test_dir);
+    }
+}
@@ -0,0 +1,79 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#[allow(dead_code)]
+mod common;
+
+use common::{TestNode, PASSWORD};
+use uuid::Uuid;
+use wallet::dispatch;
+use wallet::errors::{ErrorCategory, ErrorResponse};
+
+/// Error of a failed call as the bindings hand it to the app
+fn error_response(result: Result<String, wallet::Error>) -> ErrorResponse {
+    let error = result.expect_err("call should fail");
+    serde_json::from_str(&error.to_json()).unwrap()
+}
+
+#[test]
+fn dispatch_returns_errors_for_inputs_which_used_to_panic() {
+    let mut node = TestNode::new("dispatch_returns_errors_for_inputs_which_used_to_panic");
+    let opened = node.add_wallet("wallet");
+    node.start();
+    wallet::close_wallet(&opened).unwrap();
+
+    let params = serde_json::json!({
+        "config": node.config("wallet"),
+        "password": PASSWORD,
+    });
+    let resp: wallet::OpenWalletResponse =
+        serde_json::from_str(&dispatch::call(0, "open_wallet", &params.to_string()).unwrap())
+            .unwrap();
+    let call = |method: &str, params: serde_json::Value| {
+        error_response(dispatch::call(resp.wallet, method, &params.to_string()))
+    };
+
+    // The first tx log entry of the slate was indexed without a check
+    let tx_slate_id = Uuid::new_v4().to_string();
+    let error = call("tx_post", serde_json::json!({ "tx_slate_id": tx_slate_id }));
+    assert_eq!(error.code, "tx_doesnt_exist");
+    assert_eq!(error.category, ErrorCategory::Transaction);
+    assert!(error.message.contains(&tx_slate_id));
+
+    // The listen address was parsed with `expect`
+    let error = call(
+        "start_listen_with_http",
+        serde_json::json!({ "api_listen_addr": "not an address" }),
+    );
+    assert_eq!(error.code, "generic");
+    assert_eq!(error.category, ErrorCategory::Internal);
+    assert!(error.message.contains("not an address"));
+
+    dispatch::call(resp.wallet, "close_wallet", "").unwrap();
+    let error = error_response(dispatch::call(resp.wallet, "get_grin_address", ""));
+    assert_eq!(error.code, "invalid_handle");
+    assert_eq!(error.category, ErrorCategory::InvalidHandle);
+}
+
+#[test]
+fn dispatch_returns_errors_for_malformed_calls() {
+    let error = error_response(dispatch::call(0, "no_such_method", ""));
+    assert_eq!(error.code, "unknown_method");
+    assert_eq!(error.category, ErrorCategory::InvalidInput);
+
+    let error = error_response(dispatch::call(0, "seed_new", "not json"));
+    assert_eq!(error.code, "invalid_params");
+    assert_eq!(error.category, ErrorCategory::InvalidInput);
+}
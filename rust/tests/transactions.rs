@@ -0,0 +1,569 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#[allow(dead_code)]
+mod common;
+
+use common::{TestNode, PASSWORD};
+use grin_core::consensus::GRIN_BASE;
+use grin_wallet_libwallet::{Slate, TxLogEntryType};
+use wallet::errors::ErrorCategory;
+use wallet::ErrorKind;
+
+const MIN_CONF: u64 = 1;
+
+#[test]
+fn send_receive_finalize_post() {
+    let mut node = TestNode::new("send_receive_finalize_post");
+    let sender = node.add_wallet("sender");
+    let config = node.wallet_config("sender");
+    let recipient = node.add_wallet("recipient");
+    node.start();
+    node.mine(&sender, 10);
+
+    let resp = wallet::txs_get(&sender, None, MIN_CONF, true).unwrap();
+    assert!(resp.refreshed);
+    assert!(resp.wallet_info.amount_currently_spendable > 0);
+
+    let amount = 2 * GRIN_BASE;
+    let created =
+        wallet::tx_create(&sender, None, &config, amount, MIN_CONF, false, None, None).unwrap();
+    let txs = created.txs;
+    assert_eq!(txs.len(), 1);
+    assert_eq!(txs[0].tx_type, TxLogEntryType::TxSent);
+    let slate_id = txs[0].tx_slate_id.unwrap().to_string();
+
+    let received = wallet::tx_receive(&recipient, None, "default", &created.slatepack).unwrap();
+    let txs = received.txs;
+    assert_eq!(txs.len(), 1);
+    assert_eq!(txs[0].tx_type, TxLogEntryType::TxReceived);
+    assert_eq!(txs[0].amount_credited, amount);
+
+    let txs = wallet::tx_finalize(&sender, None, &received.slatepack)
+        .unwrap()
+        .txs;
+    assert_eq!(txs[0].tx_slate_id.unwrap().to_string(), slate_id);
+    assert!(!txs[0].confirmed);
+
+    // The mock node mines a block with the transaction as soon as it's posted
+    wallet::tx_post(&sender, None, &slate_id).unwrap();
+
+    let resp = wallet::txs_get(&recipient, None, MIN_CONF, true).unwrap();
+    assert!(resp.refreshed);
+    assert_eq!(resp.txs.len(), 1);
+    assert!(resp.txs[0].confirmed);
+    assert_eq!(resp.wallet_info.amount_currently_spendable, amount);
+
+    let txs = wallet::tx_get(&sender, None, true, &slate_id).unwrap().txs;
+    assert!(txs[0].confirmed);
+
+    // Posting an already confirmed transaction is refused
+    assert!(wallet::tx_post(&sender, None, &slate_id).is_err());
+
+    wallet::close_wallet(&sender).unwrap();
+    wallet::close_wallet(&recipient).unwrap();
+}
+
+#[test]
+fn invoice_issue_pay_finalize() {
+    let mut node = TestNode::new("invoice_issue_pay_finalize");
+    let issuer = node.add_wallet("issuer");
+    let payer = node.add_wallet("payer");
+    let config = node.wallet_config("payer");
+    node.start();
+    node.mine(&payer, 10);
+    wallet::txs_get(&payer, None, MIN_CONF, true).unwrap();
+
+    let amount = 2 * GRIN_BASE;
+    let issued = wallet::tx_issue_invoice(&issuer, None, amount, "default").unwrap();
+    assert_eq!(issued.txs.len(), 1);
+    assert_eq!(issued.txs[0].tx_type, TxLogEntryType::TxReceived);
+    let slate_id = issued.txs[0].tx_slate_id.unwrap().to_string();
+
+    let paid =
+        wallet::tx_process_invoice(&payer, None, &config, &issued.slatepack, MIN_CONF, false)
+            .unwrap();
+    assert_eq!(paid.txs.len(), 1);
+    assert_eq!(paid.txs[0].tx_type, TxLogEntryType::TxSent);
+    assert_eq!(paid.txs[0].tx_slate_id.unwrap().to_string(), slate_id);
+
+    // The issuer finalizes the paid invoice like a response to a send
+    let txs = wallet::tx_finalize(&issuer, None, &paid.slatepack)
+        .unwrap()
+        .txs;
+    assert_eq!(txs[0].tx_slate_id.unwrap().to_string(), slate_id);
+    wallet::tx_post(&issuer, None, &slate_id).unwrap();
+
+    let resp = wallet::txs_get(&issuer, None, MIN_CONF, true).unwrap();
+    assert_eq!(resp.txs.len(), 1);
+    assert!(resp.txs[0].confirmed);
+    assert_eq!(resp.wallet_info.amount_currently_spendable, amount);
+    let txs = wallet::tx_get(&payer, None, true, &slate_id).unwrap().txs;
+    assert!(txs[0].confirmed);
+
+    // A slatepack of the standard flow is not an invoice
+    let created =
+        wallet::tx_create(&payer, None, &config, amount, MIN_CONF, false, None, None).unwrap();
+    let error =
+        wallet::tx_process_invoice(&payer, None, &config, &created.slatepack, MIN_CONF, false)
+            .map(|_| ())
+            .unwrap_err();
+    assert_eq!(error.kind().code(), "argument");
+}
+
+#[test]
+fn invoice_to_other_account() {
+    let mut node = TestNode::new("invoice_to_other_account");
+    let issuer = node.add_wallet("issuer");
+    let payer = node.add_wallet("payer");
+    let config = node.wallet_config("payer");
+    node.start();
+    node.mine(&payer, 10);
+    wallet::txs_get(&payer, None, MIN_CONF, true).unwrap();
+    wallet::account_create(&issuer, None, "savings").unwrap();
+    let default_address = wallet::get_grin_address(&issuer, None).unwrap().address;
+
+    // The invoice goes into an account which is not the active one, the
+    // paid invoice comes back encrypted to that account's address
+    let amount = 2 * GRIN_BASE;
+    let issued = wallet::tx_issue_invoice(&issuer, None, amount, "savings").unwrap();
+    assert_eq!(issued.txs.len(), 1);
+    let slate_id = issued.txs[0].tx_slate_id.unwrap().to_string();
+    let paid =
+        wallet::tx_process_invoice(&payer, None, &config, &issued.slatepack, MIN_CONF, false)
+            .unwrap();
+    assert!(wallet::slatepack_decode(&paid.slatepack).is_err());
+    let txs = wallet::tx_finalize(&issuer, None, &paid.slatepack)
+        .unwrap()
+        .txs;
+    assert_eq!(txs[0].tx_slate_id.unwrap().to_string(), slate_id);
+    // Decoding with the key of another account leaves the active one alone
+    assert_eq!(
+        wallet::get_grin_address(&issuer, None).unwrap().address,
+        default_address
+    );
+
+    wallet::wallet_set_active_account(&issuer, "savings").unwrap();
+    wallet::tx_post(&issuer, None, &slate_id).unwrap();
+    let resp = wallet::txs_get(&issuer, None, MIN_CONF, true).unwrap();
+    assert!(resp.txs[0].confirmed);
+    assert_eq!(resp.wallet_info.amount_currently_spendable, amount);
+}
+
+#[test]
+fn payment_proof_export_verify() {
+    let mut node = TestNode::new("payment_proof_export_verify");
+    let sender = node.add_wallet("sender");
+    let config = node.wallet_config("sender");
+    let recipient = node.add_wallet("recipient");
+    let other = node.add_wallet("other");
+    node.start();
+    node.mine(&sender, 10);
+    wallet::txs_get(&sender, None, MIN_CONF, true).unwrap();
+
+    let address = wallet::get_grin_address(&recipient, None).unwrap().address;
+    let amount = 2 * GRIN_BASE;
+    let created = wallet::tx_create(
+        &sender,
+        None,
+        &config,
+        amount,
+        MIN_CONF,
+        false,
+        Some(&address),
+        None,
+    )
+    .unwrap();
+    let slate_id = created.txs[0].tx_slate_id.unwrap().to_string();
+    assert!(created.txs[0].payment_proof.is_some());
+    let received = wallet::tx_receive(&recipient, None, "default", &created.slatepack).unwrap();
+    wallet::tx_finalize(&sender, None, &received.slatepack).unwrap();
+    wallet::tx_post(&sender, None, &slate_id).unwrap();
+
+    let proof = wallet::payment_proof_export(&sender, None, &slate_id)
+        .unwrap()
+        .proof;
+    assert_eq!(proof.amount, amount);
+    assert_eq!(proof.recipient_address.to_string(), address);
+    let proof_json = serde_json::to_string(&proof).unwrap();
+
+    let resp = wallet::payment_proof_verify(&sender, None, &proof_json).unwrap();
+    assert!(resp.is_sender && !resp.is_recipient);
+    let resp = wallet::payment_proof_verify(&recipient, None, &proof_json).unwrap();
+    assert!(!resp.is_sender && resp.is_recipient);
+    let resp = wallet::payment_proof_verify(&other, None, &proof_json).unwrap();
+    assert!(!resp.is_sender && !resp.is_recipient);
+
+    // A proof for another amount doesn't match the signatures
+    let mut tampered = proof;
+    tampered.amount += 1;
+    let tampered_json = serde_json::to_string(&tampered).unwrap();
+    let error = wallet::payment_proof_verify(&other, None, &tampered_json)
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(error.kind().category(), ErrorCategory::PaymentProof);
+    let error = wallet::payment_proof_verify(&other, None, "{}")
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(error.kind().code(), "payment_proof_parsing");
+
+    // Transactions created without a recipient address have no proof
+    let created =
+        wallet::tx_create(&sender, None, &config, amount, MIN_CONF, false, None, None).unwrap();
+    let slate_id = created.txs[0].tx_slate_id.unwrap().to_string();
+    assert!(wallet::payment_proof_export(&sender, None, &slate_id).is_err());
+}
+
+#[test]
+fn encrypted_slatepacks() {
+    let mut node = TestNode::new("encrypted_slatepacks");
+    let sender = node.add_wallet("sender");
+    let config = node.wallet_config("sender");
+    let recipient = node.add_wallet("recipient");
+    let other = node.add_wallet("other");
+    node.start();
+    node.mine(&sender, 10);
+    wallet::txs_get(&sender, None, MIN_CONF, true).unwrap();
+
+    let address = wallet::get_grin_address(&recipient, None).unwrap().address;
+    let amount = 2 * GRIN_BASE;
+    let created = wallet::tx_create(
+        &sender,
+        None,
+        &config,
+        amount,
+        MIN_CONF,
+        false,
+        Some(&address),
+        None,
+    )
+    .unwrap();
+    let error = wallet::slatepack_decode(&created.slatepack)
+        .map(|_| ())
+        .unwrap_err();
+    assert_eq!(error.kind().code(), "slatepack_decryption");
+    assert!(wallet::slatepack_decode_with_wallet(&other, None, &created.slatepack).is_err());
+    assert!(wallet::tx_receive(&other, None, "default", &created.slatepack).is_err());
+    assert!(wallet::slatepack_decode_with_wallet(&recipient, None, &created.slatepack).is_ok());
+
+    // The reply is encrypted to the address the sender put into the slatepack
+    let received = wallet::tx_receive(&recipient, None, "default", &created.slatepack).unwrap();
+    assert!(wallet::slatepack_decode(&received.slatepack).is_err());
+    assert!(wallet::slatepack_decode_with_wallet(&sender, None, &received.slatepack).is_ok());
+    let txs = wallet::tx_finalize(&sender, None, &received.slatepack)
+        .unwrap()
+        .txs;
+    assert_eq!(txs[0].tx_slate_id, created.txs[0].tx_slate_id);
+
+    // Without a recipient everything stays readable
+    let created =
+        wallet::tx_create(&sender, None, &config, amount, MIN_CONF, false, None, None).unwrap();
+    assert!(wallet::slatepack_decode(&created.slatepack).is_ok());
+    let received = wallet::tx_receive(&recipient, None, "default", &created.slatepack).unwrap();
+    assert!(wallet::slatepack_decode(&received.slatepack).is_ok());
+}
+
+#[test]
+fn encrypted_slatepack_to_other_account() {
+    let mut node = TestNode::new("encrypted_slatepack_to_other_account");
+    let sender = node.add_wallet("sender");
+    let config = node.wallet_config("sender");
+    let recipient = node.add_wallet("recipient");
+    node.start();
+    node.mine(&sender, 10);
+    wallet::txs_get(&sender, None, MIN_CONF, true).unwrap();
+    wallet::account_create(&recipient, None, "savings").unwrap();
+    wallet::wallet_set_active_account(&recipient, "savings").unwrap();
+    let address = wallet::get_grin_address(&recipient, None).unwrap().address;
+    wallet::wallet_set_active_account(&recipient, "default").unwrap();
+
+    // Encrypted to the address of an account which is not the active one
+    let amount = 2 * GRIN_BASE;
+    let created = wallet::tx_create(
+        &sender,
+        None,
+        &config,
+        amount,
+        MIN_CONF,
+        false,
+        Some(&address),
+        None,
+    )
+    .unwrap();
+    assert!(wallet::slatepack_decode_with_wallet(&recipient, None, &created.slatepack).is_ok());
+    let received = wallet::tx_receive(&recipient, None, "savings", &created.slatepack).unwrap();
+    assert_eq!(received.txs.len(), 1);
+    assert_eq!(received.txs[0].amount_credited, amount);
+    assert_ne!(
+        wallet::get_grin_address(&recipient, None).unwrap().address,
+        address
+    );
+
+    let txs = wallet::tx_finalize(&sender, None, &received.slatepack)
+        .unwrap()
+        .txs;
+    assert_eq!(txs[0].tx_slate_id, created.txs[0].tx_slate_id);
+    // The proof is signed with the key of the receiving account
+    let slate_id = created.txs[0].tx_slate_id.unwrap().to_string();
+    wallet::tx_post(&sender, None, &slate_id).unwrap();
+    let proof = wallet::payment_proof_export(&sender, None, &slate_id)
+        .unwrap()
+        .proof;
+    assert_eq!(proof.recipient_address.to_string(), address);
+}
+
+#[test]
+fn counterparty_addresses() {
+    let mut node = TestNode::new("counterparty_addresses");
+    let sender = node.add_wallet("sender");
+    let config = node.wallet_config("sender");
+    let recipient = node.add_wallet("recipient");
+    node.start();
+    node.mine(&sender, 10);
+    wallet::txs_get(&sender, None, MIN_CONF, true).unwrap();
+    let sender_address = wallet::get_grin_address(&sender, None).unwrap().address;
+    let recipient_address = wallet::get_grin_address(&recipient, None).unwrap().address;
+
+    let created = wallet::tx_create(
+        &sender, None, &config, GRIN_BASE, MIN_CONF, false, None, None,
+    )
+    .unwrap();
+    let slate_id = created.txs[0].tx_slate_id.unwrap().to_string();
+    let decoded = wallet::slatepack_decode(&created.slatepack).unwrap();
+    assert_eq!(decoded.sender_address, Some(sender_address.clone()));
+
+    let received = wallet::tx_receive(&recipient, None, "default", &created.slatepack).unwrap();
+    assert_eq!(received.counterparty_address, Some(sender_address.clone()));
+    let finalized = wallet::tx_finalize(&sender, None, &received.slatepack).unwrap();
+    assert_eq!(
+        finalized.counterparty_address,
+        Some(recipient_address.clone())
+    );
+
+    // Both sides keep the address of the other one with the transaction
+    let resp = wallet::txs_get(&recipient, None, MIN_CONF, false).unwrap();
+    assert_eq!(resp.counterparty_addresses[&slate_id], sender_address);
+    let resp = wallet::tx_get(&sender, None, false, &slate_id).unwrap();
+    assert_eq!(resp.counterparty_addresses[&slate_id], recipient_address);
+
+    // Coinbase outputs have no other party
+    let resp = wallet::txs_get(&sender, None, MIN_CONF, false).unwrap();
+    assert_eq!(resp.counterparty_addresses.len(), 1);
+}
+
+#[test]
+fn fee_estimate() {
+    let mut node = TestNode::new("fee_estimate");
+    let sender = node.add_wallet("sender");
+    let config = node.wallet_config("sender");
+    node.start();
+    node.mine(&sender, 10);
+    wallet::txs_get(&sender, None, MIN_CONF, true).unwrap();
+
+    let amount = GRIN_BASE;
+    let estimate =
+        wallet::tx_fee_estimate(&sender, &config, amount, MIN_CONF, false, None, None).unwrap();
+    assert_eq!(estimate.fee_base, config.accept_fee_base);
+    assert_eq!(estimate.fee, estimate.weight * estimate.fee_base);
+    assert_eq!(estimate.inputs, 1);
+    assert_eq!(estimate.outputs, config.num_change_outputs + 1);
+    assert!(estimate.total >= amount + estimate.fee);
+    let strategies = wallet::tx_strategies(&sender, &config, amount, MIN_CONF)
+        .unwrap()
+        .strategies;
+    let smallest = strategies
+        .iter()
+        .find(|s| !s.selection_strategy_is_use_all)
+        .unwrap();
+    assert_eq!(smallest.fee, estimate.fee);
+
+    let more_change = wallet::tx_fee_estimate(
+        &sender,
+        &config,
+        amount,
+        MIN_CONF,
+        false,
+        None,
+        Some(config.num_change_outputs + 2),
+    )
+    .unwrap();
+    assert_eq!(more_change.outputs, estimate.outputs + 2);
+    assert!(more_change.weight > estimate.weight);
+
+    let fee_base = 2 * config.accept_fee_base;
+    let priority = wallet::tx_fee_estimate(
+        &sender,
+        &config,
+        amount,
+        MIN_CONF,
+        false,
+        Some(fee_base),
+        None,
+    )
+    .unwrap();
+    assert_eq!(priority.fee, 2 * estimate.fee);
+
+    // The transaction pays the estimated fee
+    let created = wallet::tx_create(
+        &sender,
+        None,
+        &config,
+        amount,
+        MIN_CONF,
+        false,
+        None,
+        Some(fee_base),
+    )
+    .unwrap();
+    let tx = &created.txs[0];
+    assert_eq!(
+        tx.amount_debited - tx.amount_credited,
+        amount + priority.fee
+    );
+
+    let error = wallet::tx_fee_estimate(
+        &sender,
+        &config,
+        amount,
+        MIN_CONF,
+        false,
+        Some(config.accept_fee_base - 1),
+        None,
+    )
+    .map(|_| ())
+    .unwrap_err();
+    assert_eq!(error.kind().code(), "argument");
+    let error = wallet::tx_fee_estimate(
+        &sender,
+        &config,
+        1000 * GRIN_BASE,
+        MIN_CONF,
+        false,
+        None,
+        None,
+    )
+    .map(|_| ())
+    .unwrap_err();
+    assert_eq!(error.kind().code(), "not_enough_funds");
+}
+
+#[test]
+fn cancel_unlocks_outputs() {
+    let mut node = TestNode::new("cancel_unlocks_outputs");
+    let sender = node.add_wallet("sender");
+    let config = node.wallet_config("sender");
+    node.start();
+    node.mine(&sender, 10);
+
+    let before = wallet::txs_get(&sender, None, MIN_CONF, true)
+        .unwrap()
+        .wallet_info;
+    let txs = wallet::tx_create(
+        &sender, None, &config, GRIN_BASE, MIN_CONF, true, None, None,
+    )
+    .unwrap()
+    .txs;
+    let locked = wallet::txs_get(&sender, None, MIN_CONF, false)
+        .unwrap()
+        .wallet_info;
+    assert!(locked.amount_locked > 0);
+
+    wallet::tx_cancel(&sender, None, txs[0].id).unwrap();
+    let slate_id = txs[0].tx_slate_id.unwrap().to_string();
+    let txs = wallet::tx_get(&sender, None, false, &slate_id).unwrap().txs;
+    assert_eq!(txs[0].tx_type, TxLogEntryType::TxSentCancelled);
+
+    let after = wallet::txs_get(&sender, None, MIN_CONF, false)
+        .unwrap()
+        .wallet_info;
+    assert_eq!(after.amount_locked, 0);
+    assert_eq!(
+        after.amount_currently_spendable,
+        before.amount_currently_spendable
+    );
+
+    wallet::close_wallet(&sender).unwrap();
+}
+
+#[test]
+fn scan_restores_outputs() {
+    let mut node = TestNode::new("scan_restores_outputs");
+    let miner = node.add_wallet("miner");
+    node.start();
+    node.mine(&miner, 10);
+
+    let before = wallet::txs_get(&miner, None, MIN_CONF, true)
+        .unwrap()
+        .wallet_info;
+
+    let range = wallet::wallet_pmmr_range(&miner).unwrap();
+    let (mut last_retrieved_index, highest_index) = (range.lowest_index, range.highest_index);
+    while last_retrieved_index < highest_index {
+        last_retrieved_index =
+            wallet::wallet_scan_outputs(&miner, None, last_retrieved_index, highest_index)
+                .unwrap()
+                .last_retrieved_index;
+    }
+    assert_eq!(last_retrieved_index, highest_index);
+
+    let after = wallet::txs_get(&miner, None, MIN_CONF, false)
+        .unwrap()
+        .wallet_info;
+    assert_eq!(after.total, before.total);
+    assert_eq!(
+        after.amount_currently_spendable,
+        before.amount_currently_spendable
+    );
+
+    wallet::close_wallet(&miner).unwrap();
+}
+
+#[test]
+fn slatepack_decode_matches_created_slate() {
+    let mut node = TestNode::new("slatepack_decode_matches_created_slate");
+    let sender = node.add_wallet("sender");
+    let config = node.wallet_config("sender");
+    node.start();
+    node.mine(&sender, 10);
+
+    let amount = 3 * GRIN_BASE;
+    let created =
+        wallet::tx_create(&sender, None, &config, amount, MIN_CONF, false, None, None).unwrap();
+    let slate: Slate = wallet::slatepack_decode(&created.slatepack)
+        .unwrap()
+        .slate
+        .into();
+    assert_eq!(slate.amount, amount);
+    assert_eq!(Some(slate.id), created.txs[0].tx_slate_id);
+
+    wallet::close_wallet(&sender).unwrap();
+}
+
+#[test]
+fn open_with_wrong_password_fails() {
+    let mut node = TestNode::new("open_with_wrong_password_fails");
+    let _ = node.add_wallet("wallet");
+    node.start();
+
+    let config = node.config("wallet");
+    match wallet::open_wallet(&config, &format!("{}!", PASSWORD))
+        .map(|_| ())
+        .unwrap_err()
+        .kind()
+    {
+        ErrorKind::WrongPassword() => {}
+        kind => panic!("unexpected error kind {:?}", kind),
+    }
+}
@@ -0,0 +1,363 @@
+/**
+ * Copyright 2020 Ironbelly Devs
+ *
+ * Licensed under the Apache License, Version 2.0 (the "License");
+ * you may not use this file except in compliance with the License.
+ * You may obtain a copy of the License at
+ *
+ *      http://www.apache.org/licenses/LICENSE-2.0
+ *
+ * Unless required by applicable law or agreed to in writing, software
+ * distributed under the License is distributed on an "AS IS" BASIS,
+ * WITHOUT WARRANTIES OR CONDITIONS OF ANY KIND, either express or implied.
+ * See the License for the specific language governing permissions and
+ * limitations under the License.
+ */
+#[allow(dead_code)]
+mod common;
+
+use common::{TestNode, PASSWORD};
+use grin_core::global;
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::Duration;
+use wallet::ErrorKind;
+
+const MIN_CONF: u64 = 1;
+
+#[test]
+fn named_wallets_side_by_side() {
+    let mut node = TestNode::new("named_wallets_side_by_side");
+    let default = node.add_wallet_with_config("default", &node.shared_config("default"));
+    let personal = node.add_wallet_with_config("personal", &node.shared_config("personal"));
+    let business = node.add_wallet_with_config("business", &node.shared_config("business"));
+    node.start();
+    node.mine(&personal, 5);
+
+    let wallet_dir = node.wallet_config("shared").wallet_dir;
+    assert_eq!(
+        wallet::wallets_list(&wallet_dir).unwrap().wallets,
+        vec!["default", "business", "personal"]
+    );
+
+    // Each wallet has its own seed
+    let personal_address = wallet::get_grin_address(&personal, None).unwrap().address;
+    let business_address = wallet::get_grin_address(&business, None).unwrap().address;
+    assert_ne!(personal_address, business_address);
+    let personal_info = wallet::txs_get(&personal, None, MIN_CONF, true)
+        .unwrap()
+        .wallet_info;
+    let business_info = wallet::txs_get(&business, None, MIN_CONF, true)
+        .unwrap()
+        .wallet_info;
+    assert!(personal_info.total > 0);
+    assert_eq!(business_info.total, 0);
+
+    let business_config = node.shared_config("business");
+    assert!(wallet::wallet_delete(&business_config, "wrong").is_err());
+    assert_eq!(wallet::wallets_list(&wallet_dir).unwrap().wallets.len(), 3);
+    wallet::close_wallet(&business).unwrap();
+    wallet::wallet_delete(&business_config, PASSWORD).unwrap();
+    assert_eq!(
+        wallet::wallets_list(&wallet_dir).unwrap().wallets,
+        vec!["default", "personal"]
+    );
+    assert!(wallet::open_wallet(&business_config, PASSWORD).is_err());
+
+    // Deleting the default wallet keeps the named ones
+    wallet::close_wallet(&default).unwrap();
+    wallet::wallet_delete(&node.shared_config("default"), PASSWORD).unwrap();
+    assert_eq!(
+        wallet::wallets_list(&wallet_dir).unwrap().wallets,
+        vec!["personal"]
+    );
+
+    wallet::close_wallet(&personal).unwrap();
+}
+
+#[test]
+fn change_password() {
+    let mut node = TestNode::new("change_password");
+    let wallet = node.add_wallet("wallet");
+    node.start();
+    wallet::close_wallet(&wallet).unwrap();
+
+    let config = node.config("wallet");
+    let phrase = wallet::wallet_phrase(&node.wallet_config("wallet").data_dir(), PASSWORD)
+        .unwrap()
+        .phrase;
+    assert!(wallet::wallet_change_password(&config, "wrong", "new password").is_err());
+    wallet::wallet_change_password(&config, PASSWORD, "new password").unwrap();
+
+    let data_dir = node.wallet_config("wallet").data_dir();
+    assert!(wallet::wallet_phrase(&data_dir, PASSWORD).is_err());
+    assert_eq!(
+        wallet::wallet_phrase(&data_dir, "new password")
+            .unwrap()
+            .phrase,
+        phrase
+    );
+    let seed_dir = Path::new(&data_dir).join("wallet_data");
+    assert!(!seed_dir.join("wallet.seed.rollback").exists());
+}
+
+#[test]
+fn restore_from_phrase_as_typed() {
+    let mut node = TestNode::new("restore_from_phrase_as_typed");
+    let original = node.add_wallet("original");
+    node.start();
+    wallet::close_wallet(&original).unwrap();
+
+    let phrase = wallet::wallet_phrase(&node.wallet_config("original").data_dir(), PASSWORD)
+        .unwrap()
+        .phrase;
+    let typed = format!(" {}\n", phrase.to_uppercase().replace(' ', "  \t "));
+    wallet::wallet_init(&node.config("restored"), &typed, PASSWORD).unwrap();
+    assert_eq!(
+        wallet::wallet_phrase(&node.wallet_config("restored").data_dir(), PASSWORD)
+            .unwrap()
+            .phrase,
+        phrase
+    );
+}
+
+#[test]
+fn change_password_of_open_wallet_is_refused() {
+    let mut node = TestNode::new("change_password_of_open_wallet_is_refused");
+    let opened = node.add_wallet("wallet");
+    node.start();
+    wallet::close_wallet(&opened).unwrap();
+
+    let config = node.config("wallet");
+    let params = serde_json::json!({ "config": config, "password": PASSWORD });
+    let resp: wallet::OpenWalletResponse = serde_json::from_str(
+        &wallet::dispatch::call(0, "open_wallet", &params.to_string()).unwrap(),
+    )
+    .unwrap();
+    let change_password = || {
+        let params = serde_json::json!({
+            "config": config,
+            "old_password": PASSWORD,
+            "new_password": "new password",
+        });
+        wallet::dispatch::call(0, "wallet_change_password", &params.to_string())
+    };
+    match change_password().unwrap_err().kind() {
+        ErrorKind::WalletIsOpen(_) => {}
+        kind => panic!("unexpected error kind {:?}", kind),
+    }
+    let data_dir = node.wallet_config("wallet").data_dir();
+    assert!(wallet::wallet_phrase(&data_dir, PASSWORD).is_ok());
+
+    wallet::dispatch::call(resp.wallet, "close_wallet", "").unwrap();
+    change_password().unwrap();
+    assert!(wallet::wallet_phrase(&data_dir, "new password").is_ok());
+}
+
+#[test]
+fn backup_restores_history() {
+    let mut node = TestNode::new("backup_restores_history");
+    let opened = node.add_wallet("wallet");
+    node.start();
+    node.mine(&opened, 3);
+    let txs = wallet::txs_get(&opened, None, MIN_CONF, true).unwrap().txs;
+    assert!(!txs.is_empty());
+    wallet::close_wallet(&opened).unwrap();
+
+    let backup_file = format!("{}/wallet.backup", node.test_dir);
+    let config = node.config("wallet");
+    assert!(wallet::wallet_backup_export(&config, "wrong", &backup_file).is_err());
+    wallet::wallet_backup_export(&config, PASSWORD, &backup_file).unwrap();
+
+    let restored_config = node.config("restored");
+    assert_eq!(
+        wallet::wallet_backup_import(&restored_config, "wrong", &backup_file)
+            .unwrap_err()
+            .kind(),
+        ErrorKind::WrongPassword()
+    );
+    wallet::wallet_backup_import(&restored_config, PASSWORD, &backup_file).unwrap();
+    let restored = wallet::open_wallet(&restored_config, PASSWORD).unwrap();
+    let restored_txs = wallet::txs_get(&restored, None, MIN_CONF, false)
+        .unwrap()
+        .txs;
+    assert_eq!(restored_txs.len(), txs.len());
+    wallet::close_wallet(&restored).unwrap();
+
+    match wallet::wallet_backup_import(&restored_config, PASSWORD, &backup_file)
+        .unwrap_err()
+        .kind()
+    {
+        ErrorKind::WalletAlreadyExists(_) => {}
+        kind => panic!("unexpected error kind {:?}", kind),
+    }
+}
+
+#[test]
+fn open_errors_are_distinct() {
+    let mut node = TestNode::new("open_errors_are_distinct");
+    let opened = node.add_wallet("wallet");
+    node.start();
+    wallet::close_wallet(&opened).unwrap();
+
+    let with_account = |account: serde_json::Value| {
+        let mut config: serde_json::Value = serde_json::from_str(&node.config("wallet")).unwrap();
+        config["account"] = account;
+        config.to_string()
+    };
+    let open_error = |config: &str, password: &str| {
+        wallet::open_wallet(config, password)
+            .map(|_| ())
+            .unwrap_err()
+            .kind()
+    };
+
+    // No account in the config opens the default one
+    let opened = wallet::open_wallet(&with_account(serde_json::Value::Null), PASSWORD).unwrap();
+    wallet::close_wallet(&opened).unwrap();
+
+    assert_eq!(
+        open_error(&node.config("missing"), PASSWORD),
+        ErrorKind::WalletDoesntExist()
+    );
+    assert_eq!(
+        open_error(&node.config("wallet"), "wrong"),
+        ErrorKind::WrongPassword()
+    );
+    assert_eq!(
+        open_error(&with_account("savings".into()), PASSWORD),
+        ErrorKind::AccountNotFound("savings".to_owned())
+    );
+
+    let seed_file = Path::new(&node.wallet_config("wallet").data_dir())
+        .join("wallet_data")
+        .join("wallet.seed");
+    fs::write(&seed_file, "not a seed").unwrap();
+    match open_error(&node.config("wallet"), PASSWORD) {
+        ErrorKind::WalletDataCorrupted(_) => {}
+        kind => panic!("unexpected error kind {:?}", kind),
+    }
+}
+
+#[test]
+fn masked_wallet_requires_its_mask() {
+    let mut node = TestNode::new("masked_wallet_requires_its_mask");
+    let opened = node.add_wallet("wallet");
+    node.start();
+    let address = wallet::get_grin_address(&opened, None).unwrap().address;
+    wallet::close_wallet(&opened).unwrap();
+
+    let params = serde_json::json!({
+        "config": node.config("wallet"),
+        "password": PASSWORD,
+        "use_mask": true,
+    });
+    let resp: wallet::OpenWalletResponse = serde_json::from_str(
+        &wallet::dispatch::call(0, "open_wallet", &params.to_string()).unwrap(),
+    )
+    .unwrap();
+    let mask = resp.keychain_mask.unwrap();
+    let address_with = |mask: serde_json::Value| {
+        let params = serde_json::json!({ "keychain_mask": mask }).to_string();
+        wallet::dispatch::call(resp.wallet, "get_grin_address", &params)
+            .map(|json| serde_json::from_str::<wallet::GrinAddressResponse>(&json).unwrap())
+    };
+
+    assert_eq!(address_with(mask.clone().into()).unwrap().address, address);
+    // A valid key, but not the mask of this wallet
+    let other_mask = format!("{}01", "00".repeat(31));
+    for wrong in &[serde_json::Value::Null, other_mask.into(), "zz".into()] {
+        assert_eq!(
+            address_with(wrong.clone()).map(|_| ()).unwrap_err().kind(),
+            ErrorKind::InvalidKeychainMask()
+        );
+    }
+    // The listener would have to keep a copy of the mask
+    let params = serde_json::json!({
+        "keychain_mask": mask,
+        "api_listen_addr": "127.0.0.1:0",
+    });
+    assert_eq!(
+        wallet::dispatch::call(resp.wallet, "start_listen_with_http", &params.to_string())
+            .unwrap_err()
+            .kind(),
+        ErrorKind::WalletIsMasked()
+    );
+    wallet::dispatch::call(resp.wallet, "close_wallet", "").unwrap();
+}
+
+#[test]
+fn idle_wallet_is_locked() {
+    let mut node = TestNode::new("idle_wallet_is_locked");
+    let opened = node.add_wallet("wallet");
+    node.start();
+    wallet::close_wallet(&opened).unwrap();
+
+    let mut config: serde_json::Value = serde_json::from_str(&node.config("wallet")).unwrap();
+    config["auto_lock_timeout"] = 1.into();
+    let params = serde_json::json!({ "config": config.to_string(), "password": PASSWORD });
+    let resp: wallet::OpenWalletResponse = serde_json::from_str(
+        &wallet::dispatch::call(0, "open_wallet", &params.to_string()).unwrap(),
+    )
+    .unwrap();
+    let get_address = || wallet::dispatch::call(resp.wallet, "get_grin_address", "");
+
+    // Calls keep the wallet unlocked
+    for _ in 0..3 {
+        thread::sleep(Duration::from_millis(500));
+        get_address().unwrap();
+    }
+    thread::sleep(Duration::from_millis(1100));
+    assert_eq!(get_address().unwrap_err().kind(), ErrorKind::WalletLocked());
+    // The handle of a locked wallet still has to be closed
+    wallet::dispatch::call(resp.wallet, "close_wallet", "").unwrap();
+    assert_eq!(
+        get_address().unwrap_err().kind(),
+        ErrorKind::InvalidHandle(resp.wallet)
+    );
+}
+
+#[test]
+fn invalid_wallet_names_are_refused() {
+    let node = TestNode::new("invalid_wallet_names_are_refused");
+    for name in &["", "../escape", "with space", "a/b"] {
+        match wallet::wallet_init(&node.shared_config(name), "phrase", PASSWORD)
+            .unwrap_err()
+            .kind()
+        {
+            ErrorKind::InvalidWalletName(_) => {}
+            kind => panic!("unexpected error kind {:?} for {:?}", kind, name),
+        }
+    }
+}
+
+#[test]
+fn calls_on_other_threads_use_the_fee_base_of_the_config() {
+    let mut node = TestNode::new("calls_on_other_threads_use_the_fee_base_of_the_config");
+    let opened = node.add_wallet("wallet");
+    node.start();
+    wallet::close_wallet(&opened).unwrap();
+
+    // Opening sets the fee base of the opening thread only
+    let mut config: serde_json::Value = serde_json::from_str(&node.config("wallet")).unwrap();
+    config["accept_fee_base"] = 2_000_000.into();
+    let params = serde_json::json!({ "config": config.to_string(), "password": PASSWORD });
+    let resp: wallet::OpenWalletResponse = serde_json::from_str(
+        &wallet::dispatch::call(0, "open_wallet", &params.to_string()).unwrap(),
+    )
+    .unwrap();
+
+    thread::spawn(move || {
+        let default = global::get_accept_fee_base();
+        assert_ne!(default, 2_000_000);
+        {
+            let _active = wallet::handles::active_wallet(resp.wallet).unwrap();
+            assert_eq!(global::get_accept_fee_base(), 2_000_000);
+        }
+        assert_eq!(global::get_accept_fee_base(), default);
+    })
+    .join()
+    .unwrap();
+    wallet::dispatch::call(resp.wallet, "close_wallet", "").unwrap();
+}
@@ -0,0 +1,241 @@
+// Generated by `cargo run --features schema --bin ironbelly-schema`.
+// Do not edit, change rust/src/responses.rs instead.
+
+export const RESPONSE_VERSION = 1
+
+/** Account of a wallet with its balance */
+export interface Account {
+  info: WalletInfo
+  label: string
+  path: string
+}
+
+/** Created, renamed or activated account */
+export interface AccountResponse {
+  account: Account
+  version: number
+}
+
+/** Accounts of the wallet */
+export interface AccountsListResponse {
+  accounts: Array<Account>
+  version: number
+}
+
+/** Result of calls which don't return anything */
+export interface EmptyResponse {
+  version: number
+}
+
+/** Slatepack address of the wallet */
+export interface GrinAddressResponse {
+  address: string
+  version: number
+}
+
+/** Word of a phrase which is not in the BIP-39 word list */
+export interface InvalidWord {
+  index: number
+  suggestions: Array<string>
+  word: string
+}
+
+/** Result of checking a recovery phrase, `valid` only if every check passed */
+export interface MnemonicValidateResponse {
+  checksum_valid: boolean
+  invalid_words: Array<InvalidWord>
+  length_valid: boolean
+  valid: boolean
+  version: number
+  word_count: number
+}
+
+/** Opened wallet */
+export interface OpenWalletResponse {
+  keychain_mask?: string | null
+  version: number
+  wallet: number
+}
+
+export interface PaymentProof {
+  amount: string
+  excess: string
+  recipient_address: string
+  recipient_sig: string
+  sender_address: string
+  sender_sig: string
+}
+
+/** Payment proof of a sent transaction */
+export interface PaymentProofExportResponse {
+  proof: PaymentProof
+  version: number
+}
+
+/** Valid payment proof and the parties of it this wallet is */
+export interface PaymentProofVerifyResponse {
+  is_recipient: boolean
+  is_sender: boolean
+  version: number
+}
+
+/** Recovery phrase of a new or existing wallet */
+export interface PhraseResponse {
+  phrase: string
+  version: number
+}
+
+/** Slate contained in a slatepack */
+export interface SlatepackDecodeResponse {
+  sender_address?: string | null
+  slate: unknown
+  version: number
+}
+
+/** Started HTTP listener */
+export interface StartListenResponse {
+  api_server: number
+  version: number
+}
+
+export interface Strategy {
+  fee: number
+  selection_strategy_is_use_all: boolean
+  total: number
+}
+
+/** Created transaction and the slatepack to hand to the recipient */
+export interface TxCreateResponse {
+  slatepack: string
+  txs: Array<TxLogEntry>
+  version: number
+}
+
+/** Fee of a transaction and what it would be made of */
+export interface TxFeeEstimateResponse {
+  fee: number
+  fee_base: number
+  inputs: number
+  outputs: number
+  total: number
+  version: number
+  weight: number
+}
+
+/** Finalized transaction */
+export interface TxFinalizeResponse {
+  counterparty_address?: string | null
+  txs: Array<TxLogEntry>
+  version: number
+}
+
+/** Single transaction, `txs` is empty if it doesn't exist */
+export interface TxGetResponse {
+  counterparty_addresses: { [key: string]: string }
+  refreshed: boolean
+  txs: Array<TxLogEntry>
+  version: number
+}
+
+/** Issued invoice and the slatepack to hand to the payer */
+export interface TxIssueInvoiceResponse {
+  slatepack: string
+  txs: Array<TxLogEntry>
+  version: number
+}
+
+export interface TxLogEntry {
+  amount_credited: string
+  amount_debited: string
+  confirmation_ts?: string | null
+  confirmed: boolean
+  creation_ts: string
+  fee?: string | null
+  id: number
+  kernel_excess?: string | null
+  kernel_lookup_min_height?: string | null
+  num_inputs: number
+  num_outputs: number
+  parent_key_id: string
+  payment_proof?: unknown
+  reverted_after?: unknown
+  stored_tx?: string | null
+  ttl_cutoff_height?: string | null
+  tx_slate_id?: string | null
+  tx_type: TxLogEntryType
+}
+
+export type TxLogEntryType = "ConfirmedCoinbase" | "TxReceived" | "TxSent" | "TxReceivedCancelled" | "TxSentCancelled" | "TxReverted"
+
+/** Paid invoice and the slatepack to return to the issuer */
+export interface TxProcessInvoiceResponse {
+  slatepack: string
+  txs: Array<TxLogEntry>
+  version: number
+}
+
+/** Received transaction and the slatepack to return to the sender */
+export interface TxReceiveResponse {
+  counterparty_address?: string | null
+  slatepack: string
+  txs: Array<TxLogEntry>
+  version: number
+}
+
+/** Transaction sent to a Slatepack address */
+export interface TxSendAddressResponse {
+  slate_id: string
+  version: number
+}
+
+/** Output selection strategies available for an amount */
+export interface TxStrategiesResponse {
+  strategies: Array<Strategy>
+  version: number
+}
+
+/** Both tx log entries of a transfer between accounts */
+export interface TxTransferResponse {
+  txs: Array<TxLogEntry>
+  version: number
+}
+
+/** Transaction log and balance of the wallet */
+export interface TxsGetResponse {
+  counterparty_addresses: { [key: string]: string }
+  refreshed: boolean
+  txs: Array<TxLogEntry>
+  version: number
+  wallet_info: WalletInfo
+}
+
+export interface WalletInfo {
+  amount_awaiting_confirmation: string
+  amount_awaiting_finalization: string
+  amount_currently_spendable: string
+  amount_immature: string
+  amount_locked: string
+  amount_reverted: string
+  last_confirmed_height: string
+  minimum_confirmations: string
+  total: string
+}
+
+/** PMMR indices to scan */
+export interface WalletPmmrRangeResponse {
+  highest_index: number
+  lowest_index: number
+  version: number
+}
+
+/** Progress of a UTXO set scan */
+export interface WalletScanOutputsResponse {
+  last_retrieved_index: number
+  version: number
+}
+
+/** Names of the wallets in the wallet directory */
+export interface WalletsListResponse {
+  version: number
+  wallets: Array<string>
+}
@@ -19,8 +19,14 @@ import Config from 'react-native-config'
 import { WALLET_DATA_DIRECTORY } from 'src/common'
 import RNFS from 'react-native-fs'
 import { mockedRustTransactions } from 'src/mocks'
+import { RESPONSE_VERSION } from 'src/bridges/responses'
 
 interface IGrinBridge {
+  // Calls the wallet core dispatcher. The bridge keeps the handle and the
+  // keychain mask returned by `open_wallet` and adds them to every later call
+  // until `close_wallet`, the legacy methods below only work with wallets
+  // opened without `use_mask`.
+  call: (method: string, params: string) => Promise<string>
   seedNew: (length: number) => Promise<string>
   isWalletCreated: () => Promise<boolean>
   walletInit: (
@@ -28,6 +34,11 @@ interface IGrinBridge {
     phrase: string,
     password: string,
   ) => Promise<string>
+  walletChangePassword: (
+    config: string,
+    oldPassword: string,
+    newPassword: string,
+  ) => Promise<string>
   openWallet: (config: string, password: string) => Promise<string>
   closeWallet: () => Promise<string>
   startTor: () => Promise<string>
@@ -81,21 +92,29 @@ if (Config.DEMO_MODE) {
     ...GrinBridge,
     // Wallet
     openWallet: () => Promise.resolve(''),
-    closeWallet: () => Promise.resolve(''),
+    closeWallet: () =>
+      Promise.resolve(JSON.stringify({ version: RESPONSE_VERSION })),
     // Tor
     startTor: () => Promise.resolve(''),
     stopTor: () => Promise.resolve(''),
     // Http listener
     startListenWithHttp: () =>
-      Promise.resolve('grin1<some digits/letters here>'),
-    stopListenWithHttp: () => Promise.resolve(''),
+      Promise.resolve(
+        JSON.stringify({
+          version: RESPONSE_VERSION,
+          address: 'grin1<some digits/letters here>',
+        }),
+      ),
+    stopListenWithHttp: () =>
+      Promise.resolve(JSON.stringify({ version: RESPONSE_VERSION })),
     // Transactions
     txsGet: () =>
       Promise.resolve(
-        JSON.stringify([
-          true,
-          Object.values(mockedRustTransactions),
-          {
+        JSON.stringify({
+          version: RESPONSE_VERSION,
+          refreshed: true,
+          txs: Object.values(mockedRustTransactions),
+          wallet_info: {
             amount_awaiting_confirmation: '0',
             amount_awaiting_finalization: '0',
             amount_currently_spendable: '104751338749',
@@ -106,7 +125,7 @@ if (Config.DEMO_MODE) {
             minimum_confirmations: '10',
             total: '104751338749',
           },
-        ]),
+        }),
       ),
     isWalletCreated: () => Promise.resolve(true),
   }
@@ -120,4 +139,41 @@ if (Config.DEMO_MODE) {
   }
 }
 
+// Every bridge call resolves with one of the versioned structs from
+// `src/bridges/responses`, refuse payloads of an incompatible wallet core
+export const parseResponse = <T extends { version: number }>(
+  json: string,
+): T => {
+  const response = JSON.parse(json)
+  if (response.version !== RESPONSE_VERSION) {
+    throw new Error(
+      `Unsupported wallet response version ${response.version}, expected ${RESPONSE_VERSION}`,
+    )
+  }
+  return response as T
+}
+
+// Mirrors `ErrorResponse` in rust/src/errors.rs
+export interface ErrorResponse {
+  code: string
+  category: string
+  message: string
+  details?: unknown
+}
+
+// Failed bridge calls reject with the JSON encoded `ErrorResponse` as the
+// message, errors which didn't come from the wallet core are kept as they are
+export const parseError = (error: Error): ErrorResponse => {
+  try {
+    const response = JSON.parse(error.message)
+    if (
+      typeof response.code === 'string' &&
+      typeof response.message === 'string'
+    ) {
+      return response as ErrorResponse
+    }
+  } catch (e) {}
+  return { code: 'unknown', category: 'internal', message: error.message }
+}
+
 export default WalletBridge
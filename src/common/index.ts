@@ -117,8 +117,8 @@ export const mapRustOutputStrategy = (
 }
 export const mapPmmrRange = (pR: RustPmmrRange): PmmrRange => {
   return {
-    lastRetrievedIndex: pR[0],
-    highestIndex: pR[1],
+    lastRetrievedIndex: pR.lowest_index,
+    highestIndex: pR.highest_index,
   }
 }
 export const getConfigForRust = (state: RootState) => {
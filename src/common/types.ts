@@ -25,6 +25,7 @@ import { TxReceiveActions } from 'src/modules/tx/receive'
 import { TorActions } from 'src/modules/tor'
 import { RootState } from './redux'
 import { PayloadAction } from '@reduxjs/toolkit'
+import { WalletPmmrRangeResponse } from 'src/bridges/responses'
 
 export type txListRequestAction = {
   type: 'TX_LIST_REQUEST'
@@ -596,7 +597,7 @@ export type RustOutputStrategy = {
   total: string
   fee: string
 }
-export type RustPmmrRange = Array<number> // Redux
+export type RustPmmrRange = WalletPmmrRangeResponse
 
 export type Error = {
   code?: number
@@ -51,7 +51,16 @@ import {
 } from 'src/common/types'
 import { getNavigation } from './navigation'
 import { RootState } from 'src/common/redux'
-import WalletBridge from 'src/bridges/wallet'
+import WalletBridge, { parseError, parseResponse } from 'src/bridges/wallet'
+import {
+  TxCreateResponse,
+  TxFinalizeResponse,
+  TxGetResponse,
+  TxReceiveResponse,
+  TxSendAddressResponse,
+  TxStrategiesResponse,
+  TxsGetResponse,
+} from 'src/bridges/responses'
 
 export type ListState = {
   data: Array<Tx>;
@@ -239,8 +248,8 @@ export const sideEffects = {
       const data = await WalletBridge.txsGet(
         getConfigForRust(store.getState()).minimum_confirmations,
         action.refreshFromNode,
-      ).then(JSON.parse)
-      let mappedData = data[1]
+      ).then(json => parseResponse<TxsGetResponse>(json))
+      let mappedData = data.txs
         .filter((tx: RustTx) => tx.tx_type.indexOf('Cancelled') === -1)
         .map((tx: RustTx) => {
           let pos = finalized.indexOf(tx.tx_slate_id)
@@ -310,15 +319,16 @@ export const sideEffects = {
       store.dispatch({
         type: 'TX_LIST_SUCCESS',
         data: mappedData,
-        isRefreshed: data[0],
-        balance: data[2],
+        isRefreshed: data.refreshed,
+        balance: data.wallet_info,
       })
     } catch (e) {
+      const error = parseError(e)
       store.dispatch({
         type: 'TX_LIST_FAILURE',
-        message: e.message,
+        message: error.message,
       })
-      log(e, true)
+      log(error, true)
     }
   },
   ['TX_CANCEL_REQUEST']: (action: txCancelRequestAction, store: Store) => {
@@ -338,34 +348,34 @@ export const sideEffects = {
           refreshFromNode: false,
         })
       })
-      .catch(error => {
-        const e = JSON.parse(error.message)
+      .catch(e => {
+        const error = parseError(e)
         store.dispatch({
           type: 'TX_CANCEL_FAILURE',
           code: 1,
-          message: error,
+          message: error.message,
         })
-        log(e, true)
+        log(error, true)
       })
   },
   ['TX_GET_REQUEST']: async (action: txGetRequestAction, store: Store) => {
     return WalletBridge.txGet(true, action.txSlateId)
-      .then((json: string) => JSON.parse(json))
+      .then((json: string) => parseResponse<TxGetResponse>(json))
       .then(result => {
         store.dispatch({
           type: 'TX_GET_SUCCESS',
-          isRefreshed: result[0],
-          tx: result[1][0],
+          isRefreshed: result.refreshed,
+          tx: result.txs[0],
         })
       })
-      .catch(error => {
-        const e = JSON.parse(error.message)
+      .catch(e => {
+        const error = parseError(e)
         store.dispatch({
           type: 'TX_GET_FAILURE',
           code: 1,
-          message: error,
+          message: error.message,
         })
-        log(e, true)
+        log(error, true)
       })
   },
   ['TX_CREATE_REQUEST']: async (
@@ -378,7 +388,10 @@ export const sideEffects = {
         getConfigForRust(store.getState()).minimum_confirmations,
         action.selectionStrategyIsUseAll,
       )
-      const [[rustTx], slatepack] = JSON.parse(jsonResponse)
+      const {
+        txs: [rustTx],
+        slatepack,
+      } = parseResponse<TxCreateResponse>(jsonResponse)
       const tx = mapRustTx(rustTx)
       store.dispatch({
         type: 'TX_CREATE_SUCCESS',
@@ -400,7 +413,8 @@ export const sideEffects = {
         showLoader: false,
         refreshFromNode: false,
       })
-    } catch (error) {
+    } catch (e) {
+      const error = parseError(e)
       store.dispatch({
         type: 'TX_CREATE_FAILURE',
         message: error.message,
@@ -414,12 +428,12 @@ export const sideEffects = {
   ) => {
     try {
       const finalized = await getArrayFromStorage('@finalizedTxs')
-      const slateId = await WalletBridge.txSendAddress(
+      const { slate_id: slateId } = await WalletBridge.txSendAddress(
         action.amount,
         getConfigForRust(store.getState()).minimum_confirmations,
         action.selectionStrategyIsUseAll,
         action.address,
-      ).then(JSON.parse)
+      ).then(json => parseResponse<TxSendAddressResponse>(json))
       finalized.push(slateId)
       await AsyncStorage.setItem('@finalizedTxs', JSON.stringify(finalized))
       store.dispatch({
@@ -433,7 +447,9 @@ export const sideEffects = {
         txSlateId: slateId,
       })
     } catch (e) {
-      if (e.message.indexOf('HostUnreachable') !== -1) {
+      const error = parseError(e)
+      // The recipient can't be reached over Tor, fall back to a Slatepack
+      if (error.category === 'node_unreachable') {
         store.dispatch({
           type: 'TX_CREATE_REQUEST',
           amount: action.amount,
@@ -442,9 +458,9 @@ export const sideEffects = {
       } else {
         store.dispatch({
           type: 'TX_SEND_ADDRESS_FAILURE',
-          message: e.message,
+          message: error.message,
         })
-        log(e, true)
+        log(error, true)
       }
     }
   },
@@ -471,11 +487,12 @@ export const sideEffects = {
         })
       }, 3000)
     } catch (e) {
+      const error = parseError(e)
       store.dispatch({
         type: 'TX_POST_FAILURE',
-        message: e.message,
+        message: error.message,
       })
-      log(e, true)
+      log(error, true)
     }
   },
   ['TX_POST_CLOSE']: async (_action: txPostCloseAction, store: Store) => {
@@ -491,10 +508,13 @@ export const sideEffects = {
   ) => {
     try {
       const received = await getArrayFromStorage('@receivedTxs')
-      const [[rustTx], slatepack] = await WalletBridge.txReceive(
+      const {
+        txs: [rustTx],
+        slatepack,
+      } = await WalletBridge.txReceive(
         getConfigForRust(store.getState()).account,
         action.slatepack,
-      ).then((json: string) => JSON.parse(json))
+      ).then((json: string) => parseResponse<TxReceiveResponse>(json))
       const tx = mapRustTx(rustTx)
       received.push(tx.slateId)
       await AsyncStorage.setItem('@receivedTxs', JSON.stringify(received))
@@ -520,11 +540,12 @@ export const sideEffects = {
         refreshFromNode: false,
       })
     } catch (e) {
+      const error = parseError(e)
       store.dispatch({
         type: 'TX_RECEIVE_FAILURE',
-        message: e.message,
+        message: error.message,
       })
-      log(e, true)
+      log(error, true)
     }
   },
   ['TX_FINALIZE_REQUEST']: async (
@@ -535,8 +556,10 @@ export const sideEffects = {
       const finalized = await getArrayFromStorage('@finalizedTxs')
 
       try {
-        const [rustTx] = await WalletBridge.txFinalize(action.slatepack).then(
-          JSON.parse,
+        const {
+          txs: [rustTx],
+        } = await WalletBridge.txFinalize(action.slatepack).then(json =>
+          parseResponse<TxFinalizeResponse>(json),
         )
         // this hack is needed until TxFinalized is not natively supported
         const tx = mapRustTx({ ...rustTx, tx_type: 'TxFinalized' })
@@ -557,12 +580,8 @@ export const sideEffects = {
           })
         }
       } catch (e) {
-        console.log(e.message)
-        if (
-          e.message.indexOf(
-            'LibWallet Error: Wallet store error: DB Not Found Error: Slate id: ',
-          ) !== -1
-        ) {
+        // The context of the slate is removed once it has been finalized
+        if (parseError(e).code === 'not_found') {
           log(
             {
               message:
@@ -575,11 +594,12 @@ export const sideEffects = {
         }
       }
     } catch (e) {
+      const error = parseError(e)
       store.dispatch({
         type: 'TX_FINALIZE_FAILURE',
-        message: e.message,
+        message: error.message,
       })
-      log(e, true)
+      log(error, true)
     }
   },
   ['SLATE_SET_REQUEST']: (action: slateSetRequestAction, store: Store) => {
@@ -634,8 +654,8 @@ export const sideEffects = {
       action.amount,
       getConfigForRust(store.getState()).minimum_confirmations,
     )
-      .then((json: string) => JSON.parse(json))
-      .then(outputStrategies => {
+      .then((json: string) => parseResponse<TxStrategiesResponse>(json))
+      .then(({ strategies: outputStrategies }) => {
         if (!outputStrategies.length) {
           throw new Error('Not enough funds')
         }
@@ -650,11 +670,11 @@ export const sideEffects = {
           ),
         })
       })
-      .catch((error: Error) => {
+      .catch((e: Error) => {
         store.dispatch({
           type: 'TX_FORM_OUTPUT_STRATEGIES_FAILURE',
           code: 1,
-          message: error.message,
+          message: parseError(e).message,
         })
       })
   },
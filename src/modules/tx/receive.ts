@@ -20,7 +20,8 @@ import { from, EMPTY } from 'rxjs'
 import { RootState } from 'src/common/redux'
 import { Epic, combineEpics, ofType } from 'redux-observable'
 import { Action, valueof } from 'src/common/types'
-import WalletBridge from 'src/bridges/wallet'
+import WalletBridge, { parseResponse } from 'src/bridges/wallet'
+import { GrinAddressResponse } from 'src/bridges/responses'
 
 export type State = {
   address: string | undefined
@@ -49,7 +50,10 @@ export const startHttpListenEpic: Epic<Action, Action, RootState> = (action$) =>
     mergeMap(() =>
       from(WalletBridge.startListenWithHttp('0.0.0.0:3415')).pipe(
         map(
-          (address: string) => txReceiveActions.setAddress(address) as Action,
+          (json: string) =>
+            txReceiveActions.setAddress(
+              parseResponse<GrinAddressResponse>(json).address,
+            ) as Action,
         ),
         catchError((error) => {
           console.log(error)
@@ -41,7 +41,12 @@ import {
 } from 'src/common'
 import RNFS from 'react-native-fs'
 import { WALLET_DATA_DIRECTORY, TOR_DIRECTORY } from 'src/common'
-import WalletBridge from 'src/bridges/wallet'
+import WalletBridge, { parseResponse } from 'src/bridges/wallet'
+import {
+  PhraseResponse,
+  WalletPmmrRangeResponse,
+  WalletScanOutputsResponse,
+} from 'src/bridges/responses'
 const MAX_RETRIES = 10
 export const RECOVERY_LIMIT = 1000
 const PMMR_RANGE_UPDATE_INTERVAL = 60 * 1000 // roughly one block
@@ -320,7 +325,9 @@ export const sideEffects = {
 
     try {
       const range = mapPmmrRange(
-        JSON.parse(await WalletBridge.walletPmmrRange()),
+        parseResponse<WalletPmmrRangeResponse>(
+          await WalletBridge.walletPmmrRange(),
+        ),
       )
       store.dispatch({
         type: 'WALLET_SCAN_PMMR_RANGE_SUCCESS',
@@ -378,12 +385,13 @@ export const sideEffects = {
     await checkWalletDataDirectory()
 
     try {
-      const newlastRetrievedIndex = JSON.parse(
-        await WalletBridge.walletScanOutputs(
-          lastRetrievedIndex ?? 0,
-          highestIndex,
-        ),
-      )
+      const { last_retrieved_index: newlastRetrievedIndex } =
+        parseResponse<WalletScanOutputsResponse>(
+          await WalletBridge.walletScanOutputs(
+            lastRetrievedIndex ?? 0,
+            highestIndex,
+          ),
+        )
       store.dispatch({
         type: 'WALLET_SCAN_OUTPUTS_SUCCESS',
         lastRetrievedIndex: newlastRetrievedIndex,
@@ -456,7 +464,8 @@ export const sideEffects = {
       // TODO: Add password here
       action.password,
     )
-      .then((phrase: string) => {
+      .then((json: string) => {
+        const { phrase } = parseResponse<PhraseResponse>(json)
         store.dispatch({
           type: 'WALLET_PHRASE_SUCCESS',
           phrase,
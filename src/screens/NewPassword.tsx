@@ -29,7 +29,8 @@ import {
   UnderHeaderBlockText,
 } from 'src/common'
 import { Button, Text } from 'src/components/CustomFont'
-import WalletBridge from 'src/bridges/wallet'
+import WalletBridge, { parseResponse } from 'src/bridges/wallet'
+import { PhraseResponse } from 'src/bridges/responses'
 
 type Props = NavigationProps<'NewPassword'> & {
   error: Error | undefined | null;
@@ -94,7 +95,9 @@ function NewPassword({ route, setIsNew, navigation, newWallet }: Props) {
             title={'Continue'}
             onPress={async () => {
               if (newWallet) {
-                const mnemonic = await WalletBridge.seedNew(32)
+                const { phrase: mnemonic } = parseResponse<PhraseResponse>(
+                  await WalletBridge.seedNew(32),
+                )
                 navigation.navigate('ViewPaperKey', {
                   fromSettings: false,
                   mnemonic,
@@ -31,7 +31,8 @@ import { NavigationProps } from 'src/common/types'
 import * as Keychain from 'react-native-keychain'
 import { passwordScreenMode } from 'src/modules/navigation'
 import { useSelector } from 'src/common/redux'
-import WalletBridge from 'src/bridges/wallet'
+import WalletBridge, { parseError, parseResponse } from 'src/bridges/wallet'
+import { PhraseResponse } from 'src/bridges/responses'
 import sleep from 'sleep-promise'
 import { useDispatch } from 'react-redux'
 
@@ -41,6 +42,13 @@ type Props = {
   biometryType: string | undefined | null
 } & NavigationProps<'Password'>
 
+// Only the wrong password error is expected on this screen, show the message
+// of anything else
+const openWalletErrorText = (error: Error) => {
+  const { category, message } = parseError(error)
+  return category === 'wrong_password' ? 'Wrong password' : message
+}
+
 function Password({ navigation, route }: Props) {
   const dispatch = useDispatch()
   const [password, setPassword] = useState('')
@@ -52,13 +60,13 @@ function Password({ navigation, route }: Props) {
   )
   const configForWalletRust = useSelector(getConfigForRust)
 
-  const handleWrongPassword = async () => {
+  const handleWrongPassword = async (text = 'Wrong password') => {
     await sleep(1000)
     setInProgress(false)
     setPassword('')
     dispatch({
       type: 'TOAST_SHOW',
-      text: 'Wrong password',
+      text,
     })
     await sleep(3000)
     dispatch({
@@ -87,16 +95,18 @@ function Password({ navigation, route }: Props) {
             type: 'SET_WALLET_OPEN',
           })
         } catch (e) {
-          handleWrongPassword()
+          handleWrongPassword(openWalletErrorText(e))
         }
         break
       }
       case passwordScreenMode.PAPER_KEY: {
         try {
           setInProgress(true)
-          const mnemonic = await WalletBridge.walletPhrase(
-            configForWalletRust.wallet_dir,
-            password,
+          const { phrase: mnemonic } = parseResponse<PhraseResponse>(
+            await WalletBridge.walletPhrase(
+              configForWalletRust.wallet_dir,
+              password,
+            ),
           )
           navigation.replace('ViewPaperKey', {
             mnemonic,